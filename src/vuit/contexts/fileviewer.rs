@@ -1,6 +1,7 @@
-use crate::vuit::contexts::terminal::send_cmd_to_proc_term;
-use crate::vuit::ui::{dispatch_render, next_colorscheme};
-use crate::vuit::utils::grab_config_color;
+use crate::vuit::contexts::tasks::open_tasks;
+use crate::vuit::editor::Position;
+use crate::vuit::ui::next_colorscheme;
+use crate::vuit::utils::{grab_config_color, split_search_result};
 use crate::vuit::{Context, Focus, Vuit};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::*;
@@ -11,7 +12,6 @@ use ratatui::{
     widgets::{Block, List},
 };
 use std::env;
-use std::process::Command;
 
 pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
     let area_height = chunks[1].height as usize;
@@ -23,7 +23,6 @@ pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
         0
     };
 
-    if Focus::Filelist == app.switch_focus {}
     let start = if selected >= area_height {
         selected + 1 - area_height
     } else {
@@ -36,10 +35,12 @@ pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
     let truncated: Vec<String> = visible
         .iter()
         .map(|line| {
-            if line.len() > (area_width - 5) {
-                format!("…{}", &line[line.len() - (area_width - 5)..])
+            let flag = app.mark_flag(line);
+            let width = area_width.saturating_sub(5 + flag.len());
+            if line.len() > width {
+                format!("{}…{}", flag, &line[line.len() - width..])
             } else {
-                line.clone()
+                format!("{}{}", flag, line)
             }
         })
         .collect();
@@ -49,7 +50,7 @@ pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
     }
 
     let block = Block::bordered()
-        .title(Line::from(" Files ").centered())
+        .title(Line::from(app.marked_title("Files")).centered())
         .border_set(border::ROUNDED);
 
     let list = List::new(truncated)
@@ -138,74 +139,34 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::NONE,
             ..
         } => {
-            match app.switch_focus {
+            if !app.marked.is_empty() {
+                app.open_marked(terminal);
+                return;
+            }
+
+            let (file_path, position) = match app.switch_focus {
                 Focus::Recentfiles => {
-                    if app.hltd_file >= app.recent_files.len() {
+                    let Some(file_path) = app.recent_files.get(app.hltd_file) else {
                         return;
-                    }
-                    if std::env::var("TMUX").is_ok() {
-                        let tmux_cmd = format!(
-                            "tmux split-window -h '{}' '{}' \\; resize-pane -t ! -x $(( $(tput cols) * 20/100 ))",
-                            &app.config.editor, &app.recent_files[app.hltd_file]
-                        );
-                        let _ = Command::new("sh")
-                            .args(["-c", &tmux_cmd])
-                            .status()
-                            .expect("Failed to start selected editor");
-                    } else {
-                        let _ = Command::new(&app.config.editor)
-                            .arg(&app.recent_files[app.hltd_file])
-                            .status()
-                            .expect("Failed to start selected editor");
-                    }
+                    };
+                    (file_path.clone(), None)
                 }
                 Focus::Filelist => {
-                    if app.hltd_file >= app.file_list.len() {
+                    let Some(file_path) = app.file_list.get(app.hltd_file) else {
                         return;
-                    }
-                    if std::env::var("TMUX").is_ok() {
-                        let tmux_cmd = format!(
-                            "tmux split-window -h '{}' '{}' \\; resize-pane -t ! -x $(( $(tput cols) * 20/100 ))",
-                            &app.config.editor, &app.file_list[app.hltd_file]
-                        );
-                        let _ = Command::new("sh")
-                            .args(["-c", &tmux_cmd])
-                            .status()
-                            .expect("Failed to start selected editor");
-                    } else {
-                        let _ = Command::new(&app.config.editor)
-                            .arg(&app.file_list[app.hltd_file])
-                            .status()
-                            .expect("Failed to start selected editor");
-                    }
-                    let _ = terminal.clear();
-                    let _ = terminal.draw(|frame| dispatch_render(app, frame));
+                    };
+                    (file_path.clone(), None)
                 }
                 Focus::Filestrlist => {
-                    if app.hltd_file >= app.file_str_list.len() {
+                    let Some(entry) = app.file_str_list.get(app.hltd_file) else {
                         return;
-                    }
-                    let file_path = &app.file_str_list[app.hltd_file]
-                        .split_once(':')
-                        .map(|(before, _)| before)
-                        .unwrap_or(app.file_str_list[app.hltd_file].as_str());
-                    if std::env::var("TMUX").is_ok() {
-                        let tmux_cmd = format!(
-                            "tmux split-window -h '{}' '{}' \\; resize-pane -t ! -x $(( $(tput cols) * 20/100 ))",
-                            &app.config.editor, file_path,
-                        );
-                        let _ = Command::new("sh")
-                            .args(["-c", &tmux_cmd])
-                            .status()
-                            .expect("Failed to start selected editor");
-                    } else {
-                        let _ = Command::new(&app.config.editor)
-                            .arg(file_path)
-                            .status()
-                            .expect("Failed to start selected editor");
-                    }
+                    };
+                    let (file_path, line_number) = split_search_result(entry);
+                    let position = line_number.map(|line| Position { line, col: None });
+                    (file_path.to_string(), position)
                 }
-            }
+            };
+            app.open_in_editor(&file_path, position, terminal);
 
             if app.switch_focus == Focus::Filelist
                 && !app.recent_files.contains(&app.file_list[app.hltd_file])
@@ -217,10 +178,6 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             if app.recent_files.len() > 5 {
                 app.recent_files.remove(0);
             }
-
-            // Clear terminal on exit from editor
-            let _ = terminal.clear();
-            let _ = terminal.draw(|frame| dispatch_render(app, frame));
         }
         KeyEvent {
             code: KeyCode::Char('y'),
@@ -252,6 +209,36 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                 let _ = Vuit::set_clipboard(file_path.strip_prefix("./").unwrap_or(file_path));
             }
         },
+        KeyEvent {
+            code: KeyCode::Char('v'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Mark or unmark the highlighted entry
+            app.toggle_mark();
+        }
+        KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.mark_all();
+        }
+        KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.invert_marks();
+        }
+        KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Cycle how marked files open: buffers, tabs, splits, vsplits
+            app.config.open_layout = app.config.open_layout.next();
+        }
         KeyEvent {
             code: KeyCode::Char('f'),
             modifiers: KeyModifiers::CONTROL,
@@ -455,17 +442,16 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h"])
-                    .status()
-                    .expect("Failed to start terminal");
-            } else {
-                app.typed_input.clear();
-                app.prev_context = app.switch_context;
-                app.switch_context = Context::Terminal;
-                app.term_out.clear();
-            }
+            // A multiplexer pane when vuit runs in one, else the embedded terminal
+            app.open_shell();
+        }
+        KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // List the project's runnable tasks
+            open_tasks(app);
         }
         KeyEvent {
             code: KeyCode::Char('x'),
@@ -483,20 +469,7 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                 .expect("Path is not valid UTF-8")
                 .to_string();
 
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h", "bash", "-c", &abs_path])
-                    .status()
-                    .expect("Failed to start terminal");
-            } else {
-                app.typed_input.clear();
-                app.prev_context = app.switch_context;
-                app.switch_context = Context::Terminal;
-                app.typed_input = abs_path.clone();
-            }
-            send_cmd_to_proc_term(app);
-            app.typed_input.clear();
-            app.first_term_open = false;
+            app.run_file(abs_path);
         }
         KeyEvent {
             code: KeyCode::Char('h'),
@@ -0,0 +1,218 @@
+use crate::vuit::ui::next_colorscheme;
+use crate::vuit::utils::{clean_utf8_content, grab_config_color};
+use crate::vuit::{Context, Vuit};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::{
+    DefaultTerminal, Frame,
+    symbols::border,
+    text::Line,
+    widgets::{Block, List, ListItem},
+};
+
+pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[0]);
+
+    // Compute visible range based on review_index, minus the borders
+    let area_height = halves[0].height.saturating_sub(2) as usize;
+    let total = app.replace_edits.len();
+    let selected = app.review_index.min(total.saturating_sub(1));
+    let start = if selected >= area_height {
+        selected + 1 - area_height
+    } else {
+        0
+    };
+    let end = (start + area_height).min(total);
+    let visible = &app.replace_edits[start..end];
+
+    let rejected_style = Style::default().add_modifier(Modifier::DIM | Modifier::CROSSED_OUT);
+
+    let before: Vec<ListItem> = visible
+        .iter()
+        .map(|edit| {
+            let mark = if edit.accepted { "[x]" } else { "[ ]" };
+            let item = ListItem::new(clean_utf8_content(&format!(
+                "{} {}:{}:{}",
+                mark, edit.file, edit.line_number, edit.before
+            )));
+            if edit.accepted {
+                item
+            } else {
+                item.style(rejected_style)
+            }
+        })
+        .collect();
+
+    let after: Vec<ListItem> = visible
+        .iter()
+        .map(|edit| {
+            let item = ListItem::new(clean_utf8_content(&format!(
+                "{}:{}:{}",
+                edit.file, edit.line_number, edit.after
+            )));
+            if edit.accepted {
+                item
+            } else {
+                item.style(rejected_style)
+            }
+        })
+        .collect();
+
+    let accepted = app
+        .replace_edits
+        .iter()
+        .filter(|edit| edit.accepted)
+        .count();
+
+    let highlight = Style::default()
+        .fg(Color::White)
+        .bg(grab_config_color(&app.config.highlight_color));
+
+    let before_list = List::new(before)
+        .block(
+            Block::bordered()
+                .title(Line::from(" Before ").centered())
+                .border_set(border::ROUNDED),
+        )
+        .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
+        .highlight_style(highlight);
+
+    let after_list = List::new(after)
+        .block(
+            Block::bordered()
+                .title(
+                    Line::from(format!(" After [ {} / {} accepted ] ", accepted, total)).centered(),
+                )
+                .border_set(border::ROUNDED),
+        )
+        .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
+        .highlight_style(highlight);
+
+    app.review_state.select(Some(selected - start));
+
+    frame.render_stateful_widget(before_list, halves[0], &mut app.review_state);
+    frame.render_stateful_widget(after_list, halves[1], &mut app.review_state);
+}
+
+pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(' '),
+            modifiers: KeyModifiers::NONE,
+            ..
+        } => {
+            // Accept or reject the highlighted match
+            if let Some(edit) = app.replace_edits.get_mut(app.review_index) {
+                edit.accepted = !edit.accepted;
+            }
+        }
+        KeyEvent {
+            code: KeyCode::Tab, ..
+        } => {
+            // Accept or reject every match in the highlighted file
+            let Some(file) = app
+                .replace_edits
+                .get(app.review_index)
+                .map(|edit| edit.file.clone())
+            else {
+                return;
+            };
+
+            let accept = !app
+                .replace_edits
+                .iter()
+                .filter(|edit| edit.file == file)
+                .all(|edit| edit.accepted);
+
+            for edit in app
+                .replace_edits
+                .iter_mut()
+                .filter(|edit| edit.file == file)
+            {
+                edit.accepted = accept;
+            }
+        }
+        KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        } => {
+            // Write the accepted changes
+            app.replace_string_occurences();
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Stringsearchreplace;
+            app.hltd_file = 0;
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Back to the replace input without writing anything
+            app.replace_edits.clear();
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Stringsearchreplace;
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            // Exit when Esc is pressed
+            app.exit = true;
+        }
+        KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Down,
+            ..
+        } => {
+            // Navigate review list down
+            if app.review_index + 1 < app.replace_edits.len() {
+                app.review_index += 1;
+            }
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Up, ..
+        } => {
+            // Navigate review list up
+            app.review_index = app.review_index.saturating_sub(1);
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.preview_toggle = !app.preview_toggle;
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Char('n'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            next_colorscheme(app, terminal);
+        }
+        KeyEvent {
+            code: KeyCode::Char('h'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Help;
+        }
+        _ => {}
+    };
+}
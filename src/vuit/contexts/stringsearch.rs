@@ -1,6 +1,6 @@
-use crate::vuit::contexts::terminal::send_cmd_to_proc_term;
-use crate::vuit::ui::{dispatch_render, next_colorscheme};
-use crate::vuit::utils::grab_config_color;
+use crate::vuit::editor::Position;
+use crate::vuit::ui::next_colorscheme;
+use crate::vuit::utils::{grab_config_color, split_search_result};
 use crate::vuit::{Context, Focus, Vuit};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::*;
@@ -11,7 +11,6 @@ use ratatui::{
     widgets::{Block, List},
 };
 use std::env;
-use std::process::Command;
 
 pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
     let area_height = chunks[0].height as usize;
@@ -38,7 +37,7 @@ pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
 
     let block = if app.switch_context == Context::Stringsearch {
         Block::bordered()
-            .title(Line::from(" String Search ").centered())
+            .title(Line::from(app.marked_title("String Search")).centered())
             .border_set(border::ROUNDED)
     } else {
         Block::bordered()
@@ -46,7 +45,18 @@ pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
             .border_set(border::ROUNDED)
     };
 
-    let list = List::new(visible.to_owned())
+    let block = if app.status_msg.is_empty() {
+        block
+    } else {
+        block.title_bottom(Line::from(format!(" {} ", app.status_msg)).left_aligned())
+    };
+
+    let entries: Vec<String> = visible
+        .iter()
+        .map(|entry| format!("{}{}", app.mark_flag(entry), entry))
+        .collect();
+
+    let list = List::new(entries)
         .block(block)
         .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
         .highlight_style(
@@ -81,14 +91,25 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             code: KeyCode::Enter,
             ..
         } => {
-            if app.switch_focus == Focus::Filestrlist
+            // :load <path> reads an errorfile or compiler log into the results
+            if let Some(path) = app.typed_input.strip_prefix(":load ") {
+                let path = path.trim().to_string();
+                app.typed_input.clear();
+                app.load_errorfile(&path);
+            } else if app.switch_focus == Focus::Filestrlist && !app.marked.is_empty() {
+                app.open_marked(terminal);
+            } else if app.switch_focus == Focus::Filestrlist
                 && app.file_str_list_state.selected().is_some()
             {
-                if !app.recent_files.contains(&app.file_str_list[app.hltd_file]) {
-                    let file_path = &app.file_str_list[app.hltd_file]
+                let Some(entry) = app.file_str_list.get(app.hltd_file).cloned() else {
+                    return;
+                };
+
+                if !app.recent_files.contains(&entry) {
+                    let file_path = entry
                         .split_once(':')
                         .map(|(before, _)| before)
-                        .unwrap_or(app.file_str_list[app.hltd_file].as_str());
+                        .unwrap_or(entry.as_str());
                     app.recent_files.push(file_path.to_string());
                 }
 
@@ -96,47 +117,11 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                     app.recent_files.remove(0);
                 }
 
-                let file_path = &app.file_str_list[app.hltd_file]
-                    .split_once(':')
-                    .map(|(before, _)| before)
-                    .unwrap_or(app.file_str_list[app.hltd_file].as_str());
-
-                let linearg = if app.config.editor == "vim" || app.config.editor == "nvim" {
-                    let linenumnstr = app.file_str_list[app.hltd_file]
-                        .split_once(':')
-                        .map(|(_, after)| after)
-                        .unwrap_or(app.file_str_list[app.hltd_file].as_str());
-                    let linenum = linenumnstr
-                        .split_once(':')
-                        .map(|(before, _)| before)
-                        .unwrap_or(linenumnstr);
-
-                    format!("+{}", linenum)
-                } else {
-                    String::new()
-                };
-
-                if std::env::var("TMUX").is_ok() {
-                    let tmux_cmd = format!(
-                        "tmux split-window -h '{}' '{}' '{}' \\; resize-pane -t ! -x $(( $(tput cols) * 20/100 ))",
-                        &app.config.editor, file_path, &linearg,
-                    );
-                    let _ = Command::new("sh")
-                        .args(["-c", &tmux_cmd])
-                        .status()
-                        .expect("Failed to start selected editor");
-                } else {
-                    let _ = Command::new(&app.config.editor)
-                        .arg(linearg)
-                        .arg(file_path)
-                        .status()
-                        .expect("Failed to start selected editor");
-                }
+                let (file_path, line_number) = split_search_result(&entry);
+                let position = line_number.map(|line| Position { line, col: None });
 
                 app.file_str_list_state.select(None);
-                // Clear terminal on exit from editor
-                let _ = terminal.clear();
-                let _ = terminal.draw(|frame| dispatch_render(app, frame));
+                app.open_in_editor(file_path, position, terminal);
             } else {
                 app.start_async_search();
             }
@@ -182,12 +167,8 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h"])
-                    .status()
-                    .expect("Failed to start terminal");
-            }
+            // A multiplexer pane when vuit runs in one, else the embedded terminal
+            app.open_shell();
         }
         KeyEvent {
             code: KeyCode::Char('j'),
@@ -361,10 +342,46 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            app.current_str_filter = app.typed_input.clone();
-            app.typed_input.clear();
-            app.prev_context = app.switch_context;
-            app.switch_context = Context::Stringsearchreplace;
+            app.start_replace();
+        }
+        KeyEvent {
+            code: KeyCode::Char('e'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Toggle literal/regex matching
+            app.search_opts.regex = !app.search_opts.regex;
+        }
+        KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Cycle ignore-case, match-case and smart-case
+            app.search_opts.case = app.search_opts.case.next();
+        }
+        KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Toggle whole-word matching
+            app.search_opts.whole_word = !app.search_opts.whole_word;
+        }
+        KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Stop the running search, keeping the matches found so far
+            app.cancel_search();
+        }
+        KeyEvent {
+            code: KeyCode::Char('u'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.undo_last_replace();
         }
         KeyEvent {
             code: KeyCode::Char('n'),
@@ -373,14 +390,54 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
         } => {
             next_colorscheme(app, terminal);
         }
+        KeyEvent {
+            code: KeyCode::Char('q'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Send the marked results, else all of them, to the editor's quickfix list
+            app.send_to_quickfix(terminal);
+        }
+        KeyEvent {
+            code: KeyCode::Char('v'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Mark or unmark the highlighted result
+            app.toggle_mark();
+        }
+        KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.mark_all();
+        }
+        KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.invert_marks();
+        }
+        KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.config.open_layout = app.config.open_layout.next();
+        }
         KeyEvent {
             code: KeyCode::Char('f'),
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
+            app.cancel_search();
             app.typed_input.clear();
             app.file_str_list.clear();
+            app.marked.clear();
             app.search_progress_str.clear();
+            app.status_msg.clear();
             app.prev_context = app.switch_context;
             app.switch_context = Context::Fileviewer;
             app.file_list = app.run_search_cmd();
@@ -402,20 +459,7 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                 .expect("Path is not valid UTF-8")
                 .to_string();
 
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h", "bash", "-c", &abs_path])
-                    .status()
-                    .expect("Failed to start terminal");
-            } else {
-                app.typed_input.clear();
-                app.prev_context = app.switch_context;
-                app.switch_context = Context::Terminal;
-                app.typed_input = abs_path.clone();
-            }
-            send_cmd_to_proc_term(app);
-            app.typed_input.clear();
-            app.first_term_open = false;
+            app.run_file(abs_path);
         }
         KeyEvent {
             code: KeyCode::Char('h'),
@@ -1,10 +1,10 @@
-use crate::vuit::contexts::terminal::send_cmd_to_proc_term;
-use crate::vuit::ui::{dispatch_render, next_colorscheme};
+use crate::vuit::editor::Position;
+use crate::vuit::ui::next_colorscheme;
+use crate::vuit::utils::split_search_result;
 use crate::vuit::{Context, Focus, Vuit};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::DefaultTerminal;
 use std::env;
-use std::process::Command;
 
 pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
     match key {
@@ -14,6 +14,7 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             ..
         } => {
             app.typed_input.push(c);
+            app.preview = app.run_preview_cmd();
         }
         KeyEvent {
             code: KeyCode::Backspace,
@@ -24,6 +25,7 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             }
 
             app.typed_input.pop();
+            app.preview = app.run_preview_cmd();
         }
         KeyEvent {
             code: KeyCode::Enter,
@@ -44,49 +46,22 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                     app.recent_files.remove(0);
                 }
 
-                let file_path = &app.file_str_list[app.hltd_file]
-                    .split_once(':')
-                    .map(|(before, _)| before)
-                    .unwrap_or(app.file_str_list[app.hltd_file].as_str());
-
-                let linearg = if app.config.editor == "vim" {
-                    let linenumnstr = app.file_str_list[app.hltd_file]
-                        .split_once(':')
-                        .map(|(_, after)| after)
-                        .unwrap_or(app.file_str_list[app.hltd_file].as_str());
-                    let linenum = linenumnstr
-                        .split_once(':')
-                        .map(|(before, _)| before)
-                        .unwrap_or(linenumnstr);
-
-                    format!("+{}", linenum)
-                } else {
-                    String::new()
-                };
-
-                if std::env::var("TMUX").is_ok() {
-                    let tmux_cmd = format!(
-                        "tmux split-window -h '{}' '{}' '{}' \\; resize-pane -t ! -x $(( $(tput cols) * 20/100 ))",
-                        &app.config.editor, file_path, &linearg,
-                    );
-                    let _ = Command::new("sh")
-                        .args(["-c", &tmux_cmd])
-                        .status()
-                        .expect("Failed to start selected editor");
-                } else {
-                    let _ = Command::new(&app.config.editor)
-                        .arg(linearg)
-                        .arg(file_path)
-                        .status()
-                        .expect("Failed to start selected editor");
-                }
+                let entry = app.file_str_list[app.hltd_file].clone();
+                let (file_path, line_number) = split_search_result(&entry);
+                let position = line_number.map(|line| Position { line, col: None });
 
                 app.file_str_list_state.select(None);
-                // Clear terminal on exit from editor
-                let _ = terminal.clear();
-                let _ = terminal.draw(|frame| dispatch_render(app, frame));
+                app.open_in_editor(file_path, position, terminal);
             } else {
-                app.replace_string_occurences();
+                // Review the changes before anything is written
+                app.build_replace_edits();
+                if app.replace_edits.is_empty() {
+                    app.status_msg = "No changes to review".to_string();
+                } else {
+                    app.prev_context = app.switch_context;
+                    app.switch_context = Context::Replacereview;
+                    app.preview = app.run_preview_cmd();
+                }
             }
         }
         KeyEvent {
@@ -100,12 +75,8 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h"])
-                    .status()
-                    .expect("Failed to start terminal");
-            }
+            // A multiplexer pane when vuit runs in one, else the embedded terminal
+            app.open_shell();
         }
         KeyEvent {
             code: KeyCode::Char('p'),
@@ -283,6 +254,20 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             app.typed_input.clear();
             app.switch_context = Context::Stringsearch;
         }
+        KeyEvent {
+            code: KeyCode::Char('u'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.undo_last_replace();
+        }
+        KeyEvent {
+            code: KeyCode::Char('g'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.retry_stale_replace();
+        }
         KeyEvent {
             code: KeyCode::Char('n'),
             modifiers: KeyModifiers::CONTROL,
@@ -295,9 +280,11 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
+            app.cancel_search();
             app.typed_input.clear();
             app.file_str_list.clear();
             app.search_progress_str.clear();
+            app.status_msg.clear();
             app.current_str_filter.clear();
             app.prev_context = app.switch_context;
             app.switch_context = Context::Fileviewer;
@@ -320,20 +307,7 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
                 .expect("Path is not valid UTF-8")
                 .to_string();
 
-            if std::env::var("TMUX").is_ok() {
-                let _ = Command::new("tmux")
-                    .args(["split-window", "-h", "bash", "-c", &abs_path])
-                    .status()
-                    .expect("Failed to start terminal");
-            } else {
-                app.typed_input.clear();
-                app.prev_context = app.switch_context;
-                app.switch_context = Context::Terminal;
-                app.typed_input = abs_path.clone();
-            }
-            send_cmd_to_proc_term(app);
-            app.typed_input.clear();
-            app.first_term_open = false;
+            app.run_file(abs_path);
         }
         KeyEvent {
             code: KeyCode::Char('h'),
@@ -0,0 +1,209 @@
+use crate::vuit::contexts::terminal::run_task;
+use crate::vuit::manifests;
+use crate::vuit::ui::next_colorscheme;
+use crate::vuit::utils::grab_config_color;
+use crate::vuit::{Context, Vuit};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use itertools::Itertools;
+use ratatui::prelude::*;
+use ratatui::{
+    DefaultTerminal, Frame,
+    symbols::border,
+    text::Line,
+    widgets::{Block, List, ListItem},
+};
+
+pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
+    let items: Vec<ListItem> = app
+        .task_matches
+        .iter()
+        .map(|&i| {
+            let task = &app.tasks[i];
+            ListItem::new(format!(
+                "[{}] {} - {}",
+                task.source, task.name, task.command
+            ))
+        })
+        .collect();
+
+    let last = match &app.last_task {
+        Some(task) => format!(" Last: {} | <C-l> - Re-run ", task.name),
+        None => String::new(),
+    };
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(Line::from(format!(" Tasks [ {} ] ", app.tasks.len())).centered())
+                .title_bottom(Line::from(last).left_aligned())
+                .border_set(border::ROUNDED),
+        )
+        .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
+        .highlight_style(
+            Style::default()
+                .fg(Color::White)
+                .bg(grab_config_color(&app.config.highlight_color)),
+        );
+
+    app.task_state
+        .select((!app.task_matches.is_empty()).then_some(app.task_index));
+    frame.render_stateful_widget(list, chunks[0], &mut app.task_state);
+}
+
+pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ..
+        } => {
+            app.typed_input.push(c);
+            filter_tasks(app);
+        }
+        KeyEvent {
+            code: KeyCode::Backspace,
+            ..
+        } => {
+            if app.typed_input.is_empty() {
+                return;
+            }
+
+            app.typed_input.pop();
+            filter_tasks(app);
+        }
+        KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        } => {
+            // Run the highlighted task in its own terminal session
+            let Some(&i) = app.task_matches.get(app.task_index) else {
+                return;
+            };
+            let task = app.tasks[i].clone();
+            app.typed_input.clear();
+            run_task(app, task);
+        }
+        KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            if let Some(task) = app.last_task.clone() {
+                app.typed_input.clear();
+                run_task(app, task);
+            }
+        }
+        KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Rescan the manifests after editing them
+            open_tasks(app);
+        }
+        KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Down,
+            ..
+        } => {
+            // Navigate task list down
+            app.task_index = (app.task_index + 1).min(app.task_matches.len().saturating_sub(1));
+        }
+        KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Up, ..
+        } => {
+            // Navigate task list up
+            app.task_index = app.task_index.saturating_sub(1);
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            // Exit when Esc is pressed
+            app.exit = true;
+        }
+        KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.preview_toggle = !app.preview_toggle;
+            app.preview = app.run_preview_cmd();
+        }
+        KeyEvent {
+            code: KeyCode::Char('n'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            next_colorscheme(app, terminal);
+        }
+        KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.typed_input.clear();
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Fileviewer;
+        }
+        KeyEvent {
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.typed_input.clear();
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Terminal;
+        }
+        KeyEvent {
+            code: KeyCode::Char('h'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Help;
+        }
+        _ => {}
+    };
+}
+
+// Switches to the Tasks context with a fresh scan of the project manifests
+pub fn open_tasks(app: &mut Vuit) {
+    app.tasks = manifests::discover();
+    app.typed_input.clear();
+    filter_tasks(app);
+    if app.switch_context != Context::Tasks {
+        app.prev_context = app.switch_context;
+        app.switch_context = Context::Tasks;
+    }
+}
+
+fn filter_tasks(app: &mut Vuit) {
+    let matcher = SkimMatcherV2::default();
+
+    // Keep the manifest order while nothing is typed
+    app.task_matches = if app.typed_input.is_empty() {
+        (0..app.tasks.len()).collect()
+    } else {
+        app.tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, task)| {
+                matcher
+                    .fuzzy_match(&format!("{} {}", task.name, task.command), &app.typed_input)
+                    .map(|score| (score, i))
+            })
+            .sorted_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)))
+            .map(|(_, i)| i)
+            .collect()
+    };
+    app.task_index = 0;
+}
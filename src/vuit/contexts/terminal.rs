@@ -1,43 +1,127 @@
-use crate::vuit::ui::next_colorscheme;
-use crate::vuit::utils::remove_ansi_escape_codes;
-use crate::vuit::{Context, Vuit};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use portable_pty::{unix::UnixPtySystem, CommandBuilder, PtySize, PtySystem};
+use crate::vuit::contexts::tasks::open_tasks;
+use crate::vuit::editor::Position;
+use crate::vuit::manifests::{self, Task};
+use crate::vuit::termscreen::TermScreen;
+use crate::vuit::ui::{next_colorscheme, terminal_pane_size};
+use crate::vuit::utils::{expand_tilde, grab_config_color};
+use crate::vuit::{Context, TaskRun, TermSession, Vuit, VuitRC};
+use crate::vuit::{history, locations};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use itertools::Itertools;
+use portable_pty::{CommandBuilder, PtySize, PtySystem, unix::UnixPtySystem};
 use ratatui::prelude::*;
 use ratatui::{
+    DefaultTerminal, Frame,
     symbols::border,
     text::Line,
-    widgets::{Block, Paragraph},
-    DefaultTerminal, Frame,
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
 };
 use std::{
-    io::{BufRead, BufReader, Write},
+    collections::HashSet,
+    env, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    time::Instant,
 };
 
+// Constants
+const PTY_COLS: u16 = 200;
+const PTY_ROWS: u16 = 50;
+const WHEEL_LINES: isize = 3;
+const MAX_COMPLETIONS: usize = 8;
+// Colon commands handled by vuit instead of the shell
+const COLON_CMDS: &[&str] = &["dump", "edit", "rename", "rescan"];
+
 pub fn render(app: &mut Vuit, frame: &mut Frame, chunks: &[Rect]) {
-    if app.first_term_open {
-        app.term_out.clear();
-        app.process_out.lock().unwrap().clear();
-    } else {
-        app.term_out.clear();
-        app.term_out = render_output(app);
+    if app.term_sessions.is_empty() {
+        let block = Block::bordered()
+            .title(Line::from(" Terminal | <C-o> - Retry ").centered())
+            .title_bottom(Line::from(app.term_msg.clone()).left_aligned())
+            .border_set(border::ROUNDED);
+        frame.render_widget(block, chunks[0]);
+        return;
     }
 
-    let para = Paragraph::new(remove_ansi_escape_codes(&app.term_out))
-        .block(
-            Block::bordered()
-                .title(Line::from(" Terminal ").centered())
-                .border_set(border::ROUNDED),
-        )
+    let offset = active(app).screen.lock().unwrap().scrollback();
+
+    // One tab per session, the active one in brackets
+    let tabs = app
+        .term_sessions
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let status = match &session.task {
+                Some(TaskRun {
+                    finished: Some((exit, took)),
+                    ..
+                }) => format!(" ({}, {:.1}s)", exit, took.as_secs_f64()),
+                Some(_) => " (running)".to_string(),
+                None => String::new(),
+            };
+            if i == app.term_active {
+                format!("[{}:{}{}]", i + 1, session.name, status)
+            } else {
+                format!("{}:{}{}", i + 1, session.name, status)
+            }
+        })
+        .join(" ");
+    let mut title = format!(" Terminal {} ", tabs);
+    if offset > 0 {
+        title.push_str(&format!("[scrollback -{}] ", offset));
+    }
+    if app.term_passthrough {
+        title.push_str("[PASSTHROUGH - <C-]> to leave] ");
+    }
+    let block = Block::bordered()
+        .title(Line::from(title).centered())
+        .title_bottom(Line::from(app.term_msg.clone()).left_aligned())
+        .border_set(border::ROUNDED);
+    let inner = block.inner(chunks[0]);
+    let match_style = Style::default()
+        .fg(Color::White)
+        .bg(grab_config_color(&app.config.highlight_color));
+    let mark = app
+        .term_search_line
+        .or(app.term_error_line)
+        .map(|line| (line, match_style));
+    let lines = active(app)
+        .screen
+        .lock()
+        .unwrap()
+        .view(inner.height as usize, mark);
+
+    let para = Paragraph::new(lines)
+        .block(block)
         .style(Style::default().fg(Color::White));
 
     frame.render_widget(para, chunks[0]);
+    render_completions(app, frame, chunks);
 }
 
 pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
+    if app.term_sessions.is_empty() {
+        no_session_handler(app, key);
+        return;
+    }
+    if app.term_passthrough {
+        passthrough_handler(app, key);
+        return;
+    }
+    if app.term_search_active {
+        search_handler(app, key);
+        return;
+    }
+    if app.term_history_search.is_some() {
+        history_search_handler(app, key);
+        return;
+    }
+    if !app.term_completions.is_empty() && completion_handler(app, key) {
+        return;
+    }
+
     match key {
         KeyEvent {
             code: KeyCode::Char(c),
@@ -60,10 +144,84 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             code: KeyCode::Enter,
             ..
         } => {
-            send_cmd_to_proc_term(app);
+            // Jump back to the live screen for the new command
+            active(app).screen.lock().unwrap().scroll_to_bottom();
+            app.term_msg.clear();
+            app.term_history_index = None;
+            app.term_error_line = None;
+            if let Some((name, arg)) = colon_cmd(&app.typed_input) {
+                let (name, arg) = (name.to_string(), arg.to_string());
+                run_colon_cmd(app, &name, &arg, terminal);
+            } else {
+                remember_command(app);
+                send_cmd_to_proc_term(app);
+            }
             app.typed_input.clear();
-            app.process_out.lock().unwrap().clear();
-            app.first_term_open = false;
+        }
+        KeyEvent {
+            code: KeyCode::Up, ..
+        } => {
+            // Step back through the command history
+            let len = active(app).history.len();
+            let index = match app.term_history_index {
+                None if len == 0 => return,
+                None => {
+                    app.term_history_draft = app.typed_input.clone();
+                    len - 1
+                }
+                Some(index) => index.saturating_sub(1),
+            };
+            app.term_history_index = Some(index);
+            app.typed_input = active(app).history[index].clone();
+        }
+        KeyEvent {
+            code: KeyCode::Down,
+            ..
+        } => {
+            // Step forward, ending back at the command being typed
+            let Some(index) = app.term_history_index else {
+                return;
+            };
+            if index + 1 < active(app).history.len() {
+                app.term_history_index = Some(index + 1);
+                app.typed_input = active(app).history[index + 1].clone();
+            } else {
+                app.term_history_index = None;
+                app.typed_input = std::mem::take(&mut app.term_history_draft);
+            }
+        }
+        KeyEvent {
+            code: KeyCode::PageUp,
+            ..
+        } => {
+            let page = app.pty_size.0 as isize;
+            scroll_term(app, page);
+        }
+        KeyEvent {
+            code: KeyCode::PageDown,
+            ..
+        } => {
+            let page = app.pty_size.0 as isize;
+            scroll_term(app, -page);
+        }
+        KeyEvent {
+            code: KeyCode::Tab, ..
+        } => {
+            // Complete the word being typed from the project's files and directories
+            complete_word(app);
+            if app.term_completions.len() == 1 {
+                accept_completion(app);
+            }
+        }
+        KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Incremental search through the scrollback
+            app.term_search_active = true;
+            app.term_search.clear();
+            app.term_search_line = None;
         }
         KeyEvent {
             code: KeyCode::Esc, ..
@@ -83,8 +241,11 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            // Refresh list of available files (e.g. after adding a new file, etc, ...)
-            app.run_fd_cmd();
+            // Fuzzy search the command history, re-read for the commands of other vuit instances
+            app.term_history = history::load();
+            app.term_history_draft = app.typed_input.clone();
+            app.term_history_search = Some(String::new());
+            rank_history(app);
         }
         KeyEvent {
             code: KeyCode::Char('n'),
@@ -106,10 +267,79 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
             modifiers: KeyModifiers::CONTROL,
             ..
         } => {
-            if let Some(ref mut bash_stdin) = *app.command_sender.lock().unwrap() {
-                let _ = bash_stdin.write_all(&[0x003]);
+            write_to_term(app, &[0x003]);
+        }
+        KeyEvent {
+            code: KeyCode::Char('e'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Open the next file location printed by a compiler or test run
+            step_error_location(app, true, terminal);
+        }
+        KeyEvent {
+            code: KeyCode::Char('y'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            step_error_location(app, false, terminal);
+        }
+        KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Open another session next to the current one
+            start_term(app);
+        }
+        KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            close_session(app);
+        }
+        KeyEvent {
+            code: KeyCode::Right,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            switch_session(app, 1);
+        }
+        KeyEvent {
+            code: KeyCode::Left,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            switch_session(app, app.term_sessions.len() - 1);
+        }
+        KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.typed_input.clear();
+            open_tasks(app);
+        }
+        KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Run the last task again
+            match app.last_task.clone() {
+                Some(task) => run_task(app, task),
+                None => app.term_msg = " No task has been run yet, <C-b> lists them ".to_string(),
             }
         }
+        KeyEvent {
+            code: KeyCode::Char('g'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Send every keystroke straight to the shell until <C-]>
+            app.term_passthrough = true;
+        }
         KeyEvent {
             code: KeyCode::Char('h'),
             modifiers: KeyModifiers::CONTROL,
@@ -127,40 +357,878 @@ pub fn handler(app: &mut Vuit, key: KeyEvent, terminal: &mut DefaultTerminal) {
     };
 }
 
+// The shell failed to start, so there is nothing to type into
+fn no_session_handler(app: &mut Vuit, key: KeyEvent) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Try starting the shell again
+            start_term(app);
+        }
+        KeyEvent {
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            app.prev_context = app.switch_context;
+            app.switch_context = Context::Fileviewer;
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            app.exit = true;
+        }
+        _ => {}
+    }
+}
+
+fn search_handler(app: &mut Vuit, key: KeyEvent) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ..
+        } => {
+            app.term_search.push(c);
+            search_scrollback(app, false);
+        }
+        KeyEvent {
+            code: KeyCode::Backspace,
+            ..
+        } => {
+            app.term_search.pop();
+            app.term_search_line = None;
+            search_scrollback(app, false);
+        }
+        KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Step to the next older match
+            search_scrollback(app, true);
+        }
+        KeyEvent {
+            code: KeyCode::PageUp,
+            ..
+        } => {
+            let page = app.pty_size.0 as isize;
+            scroll_term(app, page);
+        }
+        KeyEvent {
+            code: KeyCode::PageDown,
+            ..
+        } => {
+            let page = app.pty_size.0 as isize;
+            scroll_term(app, -page);
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            // Leave the search where it scrolled to
+            app.term_search_active = false;
+            app.term_search_line = None;
+        }
+        _ => {}
+    }
+}
+
+fn history_search_handler(app: &mut Vuit, key: KeyEvent) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ..
+        } => {
+            if let Some(query) = app.term_history_search.as_mut() {
+                query.push(c);
+            }
+            rank_history(app);
+        }
+        KeyEvent {
+            code: KeyCode::Backspace,
+            ..
+        } => {
+            if let Some(query) = app.term_history_search.as_mut() {
+                query.pop();
+            }
+            rank_history(app);
+        }
+        KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+        | KeyEvent {
+            code: KeyCode::Up, ..
+        } => {
+            // Next best match
+            app.term_history_pick =
+                (app.term_history_pick + 1).min(app.term_history_matches.len().saturating_sub(1));
+        }
+        KeyEvent {
+            code: KeyCode::Down,
+            ..
+        } => {
+            app.term_history_pick = app.term_history_pick.saturating_sub(1);
+        }
+        KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        } => {
+            // Put the match on the command line to edit or run
+            if let Some(command) = app.term_history_matches.get(app.term_history_pick) {
+                app.typed_input = command.clone();
+            }
+            app.term_history_search = None;
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            app.typed_input = std::mem::take(&mut app.term_history_draft);
+            app.term_history_search = None;
+        }
+        _ => {}
+    }
+}
+
+// Keys of the completion popup, any other key closes it and is handled as usual
+fn completion_handler(app: &mut Vuit, key: KeyEvent) -> bool {
+    match key {
+        KeyEvent {
+            code: KeyCode::Tab | KeyCode::Down,
+            ..
+        } => {
+            app.term_completion_pick = (app.term_completion_pick + 1) % app.term_completions.len();
+        }
+        KeyEvent {
+            code: KeyCode::BackTab | KeyCode::Up,
+            ..
+        } => {
+            let len = app.term_completions.len();
+            app.term_completion_pick = (app.term_completion_pick + len - 1) % len;
+        }
+        KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        } => {
+            accept_completion(app);
+        }
+        KeyEvent {
+            code: KeyCode::Esc, ..
+        } => {
+            app.term_completions.clear();
+        }
+        KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ..
+        } => {
+            // Keep narrowing while typing, a space ends the word
+            app.typed_input.push(c);
+            if c == ' ' {
+                app.term_completions.clear();
+            } else {
+                complete_word(app);
+            }
+        }
+        KeyEvent {
+            code: KeyCode::Backspace,
+            ..
+        } => {
+            app.typed_input.pop();
+            complete_word(app);
+        }
+        _ => {
+            app.term_completions.clear();
+            return false;
+        }
+    }
+    true
+}
+
+// Word under completion, everything after the last space of the command line
+fn current_word(input: &str) -> &str {
+    input.rsplit(' ').next().unwrap_or(input)
+}
+
+// Files and the directories above them, built once per file scan
+pub fn completion_candidates(files: &[String]) -> Vec<String> {
+    let files: Vec<&str> = files
+        .iter()
+        .map(|file| file.trim_start_matches("./"))
+        .collect();
+    let dirs: HashSet<String> = files
+        .iter()
+        .flat_map(|file| {
+            file.match_indices('/')
+                .map(|(end, _)| format!("{}/", &file[..end]))
+        })
+        .collect();
+
+    dirs.into_iter()
+        .chain(files.into_iter().map(String::from))
+        .sorted_unstable()
+        .dedup()
+        .collect()
+}
+
+// Candidates fuzzy matching the word, best first and cut to what the popup shows
+fn fuzzy_matches(candidates: &[String], word: &str) -> Vec<String> {
+    let matcher = SkimMatcherV2::default();
+    let query = word.trim_start_matches("./");
+    candidates
+        .iter()
+        .filter_map(|path| matcher.fuzzy_match(path, query).map(|score| (score, path)))
+        .sorted_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)))
+        .take(MAX_COMPLETIONS)
+        .map(|(_, path)| path.clone())
+        .collect()
+}
+
+fn complete_word(app: &mut Vuit) {
+    let word = current_word(&app.typed_input);
+    if word.is_empty() {
+        app.term_completions.clear();
+        return;
+    }
+
+    app.term_completions = fuzzy_matches(&app.term_candidates, word);
+    app.term_completion_pick = 0;
+}
+
+fn accept_completion(app: &mut Vuit) {
+    let Some(path) = app.term_completions.get(app.term_completion_pick).cloned() else {
+        return;
+    };
+
+    let word_len = current_word(&app.typed_input).len();
+    app.typed_input.truncate(app.typed_input.len() - word_len);
+    app.typed_input.push_str(&path);
+    app.term_completions.clear();
+    app.term_error_line = None;
+}
+
+fn render_completions(app: &Vuit, frame: &mut Frame, chunks: &[Rect]) {
+    if app.term_completions.is_empty() {
+        return;
+    }
+
+    // Just above the command line, as wide as the longest candidate
+    let width = app
+        .term_completions
+        .iter()
+        .map(|path| path.len() as u16 + 4)
+        .max()
+        .unwrap_or(0)
+        .max(20)
+        .min(chunks[1].width);
+    let height = (app.term_completions.len() as u16 + 2).min(chunks[0].height);
+    let area = Rect::new(
+        chunks[1].x,
+        chunks[1].y.saturating_sub(height),
+        width,
+        height,
+    );
+
+    let items: Vec<ListItem> = app
+        .term_completions
+        .iter()
+        .map(|path| ListItem::new(path.as_str()))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(Line::from(" Complete ").left_aligned())
+                .border_set(border::ROUNDED),
+        )
+        .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
+        .highlight_style(
+            Style::default()
+                .fg(Color::White)
+                .bg(grab_config_color(&app.config.highlight_color)),
+        );
+
+    let mut state = ListState::default().with_selected(Some(app.term_completion_pick));
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+// Orders the project's history, shared by every session, by fuzzy score, most recent
+// first on ties
+fn rank_history(app: &mut Vuit) {
+    let matcher = SkimMatcherV2::default();
+    let query = app.term_history_search.clone().unwrap_or_default();
+
+    app.term_history_matches = app
+        .term_history
+        .iter()
+        .enumerate()
+        .filter_map(|(i, command)| {
+            matcher
+                .fuzzy_match(command, &query)
+                .map(|score| (score, i, command))
+        })
+        .sorted_unstable_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)))
+        .map(|(_, _, command)| command.clone())
+        .collect();
+    app.term_history_pick = 0;
+}
+
+// Adds the typed command to the active session's history and to the project's history
+// file shared by all sessions
+fn remember_command(app: &mut Vuit) {
+    let command = app.typed_input.trim().to_string();
+    if command.is_empty() {
+        return;
+    }
+
+    let active = app.term_active;
+    history::push_unique(&mut app.term_sessions[active].history, &command);
+    if let Err(e) = history::record(&command) {
+        app.term_msg = format!(" Failed to save command history: {} ", e);
+    }
+}
+
+pub fn mouse_handler(app: &mut Vuit, mouse: MouseEvent) {
+    if app.term_sessions.is_empty() {
+        return;
+    }
+
+    match mouse.kind {
+        MouseEventKind::ScrollUp => scroll_term(app, WHEEL_LINES),
+        MouseEventKind::ScrollDown => scroll_term(app, -WHEEL_LINES),
+        _ => {}
+    }
+}
+
+fn scroll_term(app: &mut Vuit, delta: isize) {
+    active(app).screen.lock().unwrap().scroll_by(delta);
+}
+
+fn search_scrollback(app: &mut Vuit, older: bool) {
+    let query = app.term_search.to_lowercase();
+    if query.is_empty() {
+        app.term_search_line = None;
+        return;
+    }
+
+    let height = app.pty_size.0 as usize;
+    let screen = active(app).screen.clone();
+    let mut screen = screen.lock().unwrap();
+    let lines = screen.lines();
+
+    // Search upwards from the current match, or from the bottom of the view
+    let from = match app.term_search_line {
+        Some(line) if older => line,
+        Some(line) => line + 1,
+        None => screen.view_range(height).1,
+    };
+    let found = lines[..from.min(lines.len())]
+        .iter()
+        .rposition(|line| line.to_lowercase().contains(&query));
+
+    match found {
+        Some(line) => {
+            app.term_search_line = Some(line);
+            screen.reveal(line, height);
+        }
+        None if !older => app.term_search_line = None,
+        None => {}
+    }
+}
+
+// Working directory of the session's shell when it differs from vuit's
+fn session_cwd(session: &TermSession) -> Option<PathBuf> {
+    let pid = session.process.process_id()?;
+    let cwd = fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+    (env::current_dir().ok()? != cwd).then_some(cwd)
+}
+
+fn step_error_location(app: &mut Vuit, forward: bool, terminal: &mut DefaultTerminal) {
+    let (patterns, invalid) =
+        locations::build_patterns(&app.config.error_presets, &app.config.error_patterns);
+    if let Some(pattern) = invalid.first() {
+        app.term_msg = format!(
+            " Invalid error pattern (needs file and line groups): {} ",
+            pattern
+        );
+        return;
+    }
+
+    // Paths are printed relative to where the shell is, which may not be where vuit runs
+    let mut dirs = Vec::from_iter(session_cwd(active(app)));
+    dirs.push(PathBuf::new());
+
+    let height = app.pty_size.0 as usize;
+    let screen = active(app).screen.clone();
+    let mut screen = screen.lock().unwrap();
+    let found = locations::find_locations(&screen.lines(), &patterns, &dirs);
+    let (top, bottom) = screen.view_range(height);
+
+    // Without a current location, start from what is on screen
+    let position = if forward {
+        let from = app.term_error_line.map(|line| line + 1).unwrap_or(top);
+        found.iter().position(|loc| loc.output_line >= from)
+    } else {
+        let before = app.term_error_line.unwrap_or(bottom);
+        found.iter().rposition(|loc| loc.output_line < before)
+    };
+    let Some(position) = position else {
+        app.term_msg = if found.is_empty() {
+            " No error locations in the terminal output ".to_string()
+        } else {
+            " No more error locations ".to_string()
+        };
+        return;
+    };
+
+    let loc = &found[position];
+    screen.reveal(loc.output_line, height);
+    drop(screen);
+
+    app.term_error_line = Some(loc.output_line);
+    app.term_msg = format!(
+        " Location {} / {}: {}:{}{} ",
+        position + 1,
+        found.len(),
+        loc.file,
+        loc.line_number,
+        loc.column
+            .map(|col| format!(":{}", col))
+            .unwrap_or_default()
+    );
+    let position = Position {
+        line: loc.line_number,
+        col: loc.column,
+    };
+    app.open_in_editor(&loc.file, Some(position), terminal);
+}
+
+// Name and argument of a colon command vuit handles, other lines starting with : are shell
+fn colon_cmd(input: &str) -> Option<(&str, &str)> {
+    let command = input.strip_prefix(':')?.trim();
+    let (name, arg) = command
+        .split_once(' ')
+        .map(|(name, arg)| (name, arg.trim()))
+        .unwrap_or((command, ""));
+
+    COLON_CMDS.contains(&name).then_some((name, arg))
+}
+
+fn run_colon_cmd(app: &mut Vuit, name: &str, arg: &str, terminal: &mut DefaultTerminal) {
+    match name {
+        "dump" => {
+            if arg.is_empty() {
+                app.term_msg = " Usage: :dump <path> ".to_string();
+                return;
+            }
+            let path = expand_tilde(arg);
+            app.term_msg = match write_scrollback(app, &path) {
+                Ok(()) => format!(" Scrollback written to {} ", path.display()),
+                Err(e) => format!(" Failed to write {}: {} ", path.display(), e),
+            };
+        }
+        "rename" => {
+            if arg.is_empty() {
+                app.term_msg = " Usage: :rename <name> ".to_string();
+                return;
+            }
+            let active = app.term_active;
+            app.term_sessions[active].name = arg.to_string();
+        }
+        // <C-r> is history search here, so the file scan is refreshed by name
+        "rescan" => {
+            app.run_fd_cmd();
+            app.term_msg = format!(" Rescanned {} files ", app.fd_list.len());
+        }
+        "edit" => {
+            let file = tempfile::Builder::new()
+                .prefix("vuit-scrollback-")
+                .suffix(".txt")
+                .tempfile();
+            let file = match file.and_then(|file| write_scrollback(app, file.path()).map(|_| file))
+            {
+                Ok(file) => file,
+                Err(e) => {
+                    app.term_msg = format!(" Failed to write the scrollback: {} ", e);
+                    return;
+                }
+            };
+            let path = file.path().display().to_string();
+            // Kept until the next :edit or until vuit exits, the editor reads it meanwhile
+            app.scrollback_file = Some(file);
+            app.open_in_editor(&path, None, terminal);
+        }
+        _ => {}
+    }
+}
+
+fn write_scrollback(app: &Vuit, path: &Path) -> io::Result<()> {
+    let mut lines = active(app).screen.lock().unwrap().lines();
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(path, content)
+}
+
+fn passthrough_handler(app: &mut Vuit, key: KeyEvent) {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(']') | KeyCode::Char('5'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        } => {
+            // Leave passthrough, some terminals report <C-]> as <C-5>
+            app.term_passthrough = false;
+        }
+        _ => {
+            let app_cursor = active(app)
+                .screen
+                .lock()
+                .unwrap()
+                .screen()
+                .application_cursor();
+            let bytes = key_to_bytes(key, app_cursor);
+            if !bytes.is_empty() {
+                write_to_term(app, &bytes);
+            }
+        }
+    }
+}
+
+// Encodes a key press the way an xterm would send it to the program
+fn key_to_bytes(key: KeyEvent, app_cursor: bool) -> Vec<u8> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    // xterm modifier parameter for cursor and function keys, 1 means none
+    let modifier = 1 + shift as u8 + 2 * alt as u8 + 4 * ctrl as u8;
+
+    let cursor = |letter: char| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[1;{}{}", modifier, letter).into_bytes()
+        } else if app_cursor {
+            format!("\x1bO{}", letter).into_bytes()
+        } else {
+            format!("\x1b[{}", letter).into_bytes()
+        }
+    };
+    let tilde = |number: u8| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[{};{}~", number, modifier).into_bytes()
+        } else {
+            format!("\x1b[{}~", number).into_bytes()
+        }
+    };
+
+    let bytes = match key.code {
+        KeyCode::Char(c) if ctrl => match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+            '@' | ' ' | '2' => vec![0x00],
+            '[' | '3' => vec![0x1b],
+            '\\' | '4' => vec![0x1c],
+            ']' | '5' => vec![0x1d],
+            '^' | '6' => vec![0x1e],
+            '_' | '-' | '7' => vec![0x1f],
+            '?' | '8' => vec![0x7f],
+            _ => c.to_string().into_bytes(),
+        },
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => cursor('A'),
+        KeyCode::Down => cursor('B'),
+        KeyCode::Right => cursor('C'),
+        KeyCode::Left => cursor('D'),
+        KeyCode::Home => cursor('H'),
+        KeyCode::End => cursor('F'),
+        KeyCode::Insert => tilde(2),
+        KeyCode::Delete => tilde(3),
+        KeyCode::PageUp => tilde(5),
+        KeyCode::PageDown => tilde(6),
+        // F1-F4 are SS3 keys, with modifiers they take the CSI 1;m form
+        KeyCode::F(n @ 1..=4) if modifier > 1 => {
+            format!("\x1b[1;{}{}", modifier, (b'P' + n - 1) as char).into_bytes()
+        }
+        KeyCode::F(n @ 1..=4) => format!("\x1bO{}", (b'P' + n - 1) as char).into_bytes(),
+        KeyCode::F(n @ 5..=12) => tilde([15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5]),
+        _ => Vec::new(),
+    };
+
+    // Alt sends the key prefixed with Escape, cursor and function keys carry it in the modifier
+    let carries_modifier = matches!(
+        key.code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Right
+            | KeyCode::Left
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Insert
+            | KeyCode::Delete
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::F(_)
+    );
+    if alt && !carries_modifier && !bytes.is_empty() {
+        [vec![0x1b], bytes].concat()
+    } else {
+        bytes
+    }
+}
+
+// Opens a new session and makes it the active one
 pub fn start_term(app: &mut Vuit) {
+    if app.pty_size == (0, 0) {
+        let (cols, rows) = crossterm::terminal::size().unwrap_or((PTY_COLS, PTY_ROWS));
+        app.pty_size = terminal_pane_size(cols, rows);
+    }
+
+    // Tabs start out named after the shell binary
+    let name = Path::new(&app.config.shell)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| app.config.shell.clone());
+    match spawn_session(&app.config, name, app.pty_size, None) {
+        Ok(session) => {
+            app.term_sessions.push(session);
+            switch_session(app, app.term_sessions.len() - 1 - app.term_active);
+        }
+        Err(e) => app.term_msg = format!(" Failed to start {}: {} ", app.config.shell, e),
+    }
+}
+
+// Runs a task in its own session, replacing the session of an earlier run of it
+pub fn run_task(app: &mut Vuit, task: Task) {
+    app.prev_context = app.switch_context;
+    app.switch_context = Context::Terminal;
+    app.typed_input.clear();
+
+    let session = match spawn_session(
+        &app.config,
+        task.name.clone(),
+        app.pty_size,
+        Some(&task.command),
+    ) {
+        Ok(session) => session,
+        Err(e) => {
+            app.term_msg = format!(" Failed to run {}: {} ", task.name, e);
+            return;
+        }
+    };
+
+    match app
+        .term_sessions
+        .iter()
+        .position(|session| session.task.is_some() && session.name == task.name)
+    {
+        Some(i) => {
+            end_session(std::mem::replace(&mut app.term_sessions[i], session));
+            app.term_active = i;
+            switch_session(app, 0);
+        }
+        None => {
+            app.term_sessions.push(session);
+            switch_session(app, app.term_sessions.len() - 1 - app.term_active);
+        }
+    }
+
+    app.term_msg = match manifests::save_last(&task) {
+        Ok(()) => String::new(),
+        Err(e) => format!(" Failed to remember the last task: {} ", e),
+    };
+    app.last_task = Some(task);
+}
+
+// Records the exit status and duration of task sessions that have finished
+pub fn poll_tasks(app: &mut Vuit) {
+    for session in &mut app.term_sessions {
+        let Some(task) = &mut session.task else {
+            continue;
+        };
+        if task.finished.is_some() {
+            continue;
+        }
+        if let Ok(Some(status)) = session.process.try_wait() {
+            let exit = match status.signal() {
+                Some(signal) => signal.to_string(),
+                None => format!("exit {}", status.exit_code()),
+            };
+            task.finished = Some((exit, task.started.elapsed()));
+        }
+    }
+}
+
+// A command makes the shell run just that and exit, otherwise it stays interactive
+fn spawn_session(
+    config: &VuitRC,
+    name: String,
+    (rows, cols): (u16, u16),
+    command: Option<&str>,
+) -> io::Result<TermSession> {
     let pty_system = UnixPtySystem::default();
     let pair = pty_system
         .openpty(PtySize {
-            rows: 20,
-            cols: 200,
+            rows,
+            cols,
             pixel_width: 0,
             pixel_height: 0,
         })
-        .expect("Failed to open PTY");
+        .map_err(io::Error::other)?;
 
-    let cmd = CommandBuilder::new("bash");
-    let child = pair.slave.spawn_command(cmd).expect("Failed to spawn bash");
-    let reader = BufReader::new(pair.master.try_clone_reader().unwrap());
-    let writer = pair.master.take_writer().unwrap();
-    let output = app.process_out.clone();
+    let mut cmd = CommandBuilder::new(&config.shell);
+    cmd.args(&config.shell_args);
+    if let Some(command) = command {
+        let script = if config.shell_init.is_empty() {
+            command.to_string()
+        } else {
+            format!("{}\n{}", config.shell_init, command)
+        };
+        cmd.args(["-c", &script]);
+    }
+    for (key, value) in &config.shell_env {
+        cmd.env(key, value);
+    }
+    // Start in the project root vuit is indexing
+    if let Ok(cwd) = env::current_dir() {
+        cmd.cwd(cwd);
+    }
+
+    let process = pair.slave.spawn_command(cmd).map_err(io::Error::other)?;
+    let mut reader = pair.master.try_clone_reader().map_err(io::Error::other)?;
+    let mut writer = pair.master.take_writer().map_err(io::Error::other)?;
+
+    if command.is_none() && !config.shell_init.is_empty() {
+        let _ = writeln!(writer, "{}", config.shell_init);
+    }
+
+    // Fresh screen per shell so a dying reader cannot draw into the new one
+    let screen = Arc::new(Mutex::new(TermScreen::new(rows, cols)));
+    let output = screen.clone();
 
     thread::spawn(move || {
-        for line in reader.lines() {
-            let mut output = output.lock().unwrap();
-            output.push(line.ok().unwrap_or_default());
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.lock().unwrap().process(&buf[..n]),
+            }
         }
     });
 
-    app.bash_process = Some(child);
-    app.command_sender = Arc::new(Mutex::new(Some(Box::new(writer))));
+    Ok(TermSession {
+        name,
+        process,
+        master: pair.master,
+        screen,
+        writer,
+        // New sessions start from the project's history, later commands stay their own
+        history: history::load(),
+        task: command.map(|_| TaskRun {
+            started: Instant::now(),
+            finished: None,
+        }),
+    })
+}
+
+// Stops a session's shell and reaps it so it does not linger as a zombie
+fn end_session(session: TermSession) {
+    let mut process = session.process;
+    // Shells that already exited refuse the signal, they still need reaping
+    let _ = process.kill();
+    thread::spawn(move || process.wait());
+}
+
+fn active(app: &Vuit) -> &TermSession {
+    &app.term_sessions[app.term_active]
+}
+
+fn write_to_term(app: &mut Vuit, bytes: &[u8]) {
+    let active = app.term_active;
+    let writer = &mut app.term_sessions[active].writer;
+    let _ = writer.write_all(bytes);
+    let _ = writer.flush();
+}
+
+// Moves forward through the sessions, wrapping around
+fn switch_session(app: &mut Vuit, step: usize) {
+    app.term_active = (app.term_active + step) % app.term_sessions.len();
+    // Search matches and passthrough belong to the session they started in
+    app.term_search_active = false;
+    app.term_search_line = None;
+    app.term_passthrough = false;
+    app.term_history_index = None;
+    app.term_history_search = None;
+    app.term_completions.clear();
+    app.term_error_line = None;
+}
+
+fn close_session(app: &mut Vuit) {
+    end_session(app.term_sessions.remove(app.term_active));
+
+    if app.term_sessions.is_empty() {
+        app.term_active = 0;
+        start_term(app);
+    } else {
+        app.term_active = app.term_active.min(app.term_sessions.len() - 1);
+        switch_session(app, 0);
+    }
+}
+
+// Rows and columns inside the Terminal pane borders for a window this wide
+// Called on Event::Resize so the shell sees the new size even while the pane is hidden.
+// A window shorter than the pane's usual height squeezes it, so rows follow the height too
+pub fn resize_to_window(app: &mut Vuit, window_cols: u16, window_rows: u16) {
+    let (rows, cols) = terminal_pane_size(window_cols, window_rows);
+    resize_term(app, rows, cols);
+}
+
+fn resize_term(app: &mut Vuit, rows: u16, cols: u16) {
+    let size = (rows.max(1), cols.max(1));
+    if app.pty_size == size {
+        return;
+    }
+    app.pty_size = size;
+
+    for session in &app.term_sessions {
+        session.screen.lock().unwrap().set_size(size.0, size.1);
+        let _ = session.master.resize(PtySize {
+            rows: size.0,
+            cols: size.1,
+            pixel_width: 0,
+            pixel_height: 0,
+        });
+    }
 }
 
+// Replaces the active session's shell, keeping its name and place in the tabs
 fn restart_terminal_session(app: &mut Vuit) {
-    if let Some(mut child) = app.bash_process.take() {
-        child.kill().expect("Failed to kill bash process");
+    let active = app.term_active;
+    let name = app.term_sessions[active].name.clone();
+    match spawn_session(&app.config, name, app.pty_size, None) {
+        Ok(mut session) => {
+            // The new shell carries on with the session's own history
+            session.history = std::mem::take(&mut app.term_sessions[active].history);
+            end_session(std::mem::replace(&mut app.term_sessions[active], session));
+            switch_session(app, 0);
+        }
+        Err(e) => app.term_msg = format!(" Failed to restart {}: {} ", app.config.shell, e),
     }
-    thread::sleep(Duration::from_millis(250));
-    start_term(app);
 }
 
 pub fn send_cmd_to_proc_term(app: &mut Vuit) {
@@ -168,7 +1236,11 @@ pub fn send_cmd_to_proc_term(app: &mut Vuit) {
     let command = app.typed_input.trim_start_matches(';').to_string();
     match command.as_str() {
         "vuit" => {
-            app.term_out = "Nice Try".to_string();
+            active(app)
+                .screen
+                .lock()
+                .unwrap()
+                .process(b"\r\nNice Try\r\n");
         }
         "exit" => {
             restart_terminal_session(app);
@@ -187,18 +1259,160 @@ pub fn send_cmd_to_proc_term(app: &mut Vuit) {
             restart_terminal_session(app);
         }
         _ => {
-            if let Some(ref mut bash_stdin) = *app.command_sender.lock().unwrap() {
-                writeln!(bash_stdin, "{}", command).unwrap_or(());
-            }
+            write_to_term(app, format!("{}\n", command).as_bytes());
         }
     }
 }
 
-fn render_output(app: &Vuit) -> String {
-    // Fetch the output from PTY (this is simplified for the example)
-    let output_str = {
-        let output = app.process_out.lock().unwrap().clone();
-        output.join("\n") // Join the lines together
-    };
-    output_str
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_colon_commands_are_taken_from_the_shell() {
+        assert_eq!(colon_cmd(":rescan"), Some(("rescan", "")));
+        assert_eq!(colon_cmd(":dump out.txt"), Some(("dump", "out.txt")));
+        assert_eq!(colon_cmd(":edit"), Some(("edit", "")));
+        assert_eq!(colon_cmd(":rename  build "), Some(("rename", "build")));
+        assert_eq!(colon_cmd(":"), None);
+        assert_eq!(colon_cmd(": > file"), None);
+        assert_eq!(colon_cmd(":make"), None);
+        assert_eq!(colon_cmd("dump x"), None);
+    }
+
+    #[test]
+    fn history_search_ranks_the_shared_history() {
+        let mut app = Vuit {
+            term_history: ["cargo build", "git status", "cargo test"]
+                .map(String::from)
+                .to_vec(),
+            term_history_search: Some("cargo".to_string()),
+            ..Default::default()
+        };
+        rank_history(&mut app);
+        assert_eq!(app.term_history_matches, ["cargo test", "cargo build"]);
+    }
+
+    #[test]
+    fn candidates_include_each_directory_once() {
+        let files = ["./src/main.rs", "./src/vuit/mod.rs", "./README.md"].map(String::from);
+        assert_eq!(
+            completion_candidates(&files),
+            [
+                "README.md",
+                "src/",
+                "src/main.rs",
+                "src/vuit/",
+                "src/vuit/mod.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn completion_is_fuzzy() {
+        let files = ["./src/main.rs", "./src/vuit/mod.rs", "./scripts/run.sh"].map(String::from);
+        let candidates = completion_candidates(&files);
+
+        assert!(fuzzy_matches(&candidates, "smr").contains(&"src/vuit/mod.rs".to_string()));
+        assert_eq!(fuzzy_matches(&candidates, "./src/v")[0], "src/vuit/");
+        assert_eq!(fuzzy_matches(&candidates, "s").len(), 6);
+        assert!(fuzzy_matches(&candidates, "xyz").is_empty());
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn cursor_keys_follow_the_application_cursor_mode() {
+        let up = key(KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(key_to_bytes(up, false), b"\x1b[A");
+        assert_eq!(key_to_bytes(up, true), b"\x1bOA");
+        assert_eq!(
+            key_to_bytes(key(KeyCode::End, KeyModifiers::NONE), true),
+            b"\x1bOF"
+        );
+    }
+
+    #[test]
+    fn modified_cursor_keys_carry_the_xterm_parameter() {
+        let ctrl_right = key(KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(key_to_bytes(ctrl_right, false), b"\x1b[1;5C");
+        // The parameter wins over application cursor mode
+        assert_eq!(key_to_bytes(ctrl_right, true), b"\x1b[1;5C");
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Left, KeyModifiers::SHIFT), false),
+            b"\x1b[1;2D"
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Up, KeyModifiers::ALT), false),
+            b"\x1b[1;3A"
+        );
+        assert_eq!(
+            key_to_bytes(
+                key(KeyCode::Delete, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+                false
+            ),
+            b"\x1b[3;6~"
+        );
+    }
+
+    #[test]
+    fn control_and_alt_characters() {
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Char('c'), KeyModifiers::CONTROL), false),
+            [0x03]
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Char('A'), KeyModifiers::CONTROL), false),
+            [0x01]
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Char(' '), KeyModifiers::CONTROL), false),
+            [0x00]
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Char('['), KeyModifiers::CONTROL), false),
+            [0x1b]
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Char('b'), KeyModifiers::ALT), false),
+            b"\x1bb"
+        );
+        assert_eq!(
+            key_to_bytes(
+                key(
+                    KeyCode::Char('x'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT
+                ),
+                false
+            ),
+            b"\x1b\x18"
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::Backspace, KeyModifiers::ALT), false),
+            b"\x1b\x7f"
+        );
+    }
+
+    #[test]
+    fn editing_and_function_keys() {
+        let plain = |code| key_to_bytes(key(code, KeyModifiers::NONE), false);
+        assert_eq!(plain(KeyCode::Enter), b"\r");
+        assert_eq!(plain(KeyCode::BackTab), b"\x1b[Z");
+        assert_eq!(plain(KeyCode::PageUp), b"\x1b[5~");
+        assert_eq!(plain(KeyCode::F(1)), b"\x1bOP");
+        assert_eq!(plain(KeyCode::F(4)), b"\x1bOS");
+        assert_eq!(plain(KeyCode::F(5)), b"\x1b[15~");
+        assert_eq!(plain(KeyCode::F(12)), b"\x1b[24~");
+        assert_eq!(
+            key_to_bytes(key(KeyCode::F(1), KeyModifiers::CONTROL), false),
+            b"\x1b[1;5P"
+        );
+        assert_eq!(
+            key_to_bytes(key(KeyCode::F(5), KeyModifiers::SHIFT), false),
+            b"\x1b[15;2~"
+        );
+        assert!(plain(KeyCode::F(13)).is_empty());
+    }
 }
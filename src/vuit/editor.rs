@@ -0,0 +1,367 @@
+use crossterm::event::DisableMouseCapture;
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::DefaultTerminal;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+// Constants
+const DEFAULT_TEMPLATE: &str = "{editor} {file}";
+// Editors with their own window, started detached unless configured otherwise
+const GUI_EDITORS: &[&str] = &["code", "codium", "gvim", "subl", "zed"];
+// Editors that take vim's -p, -o and -O flags
+const VIM_EDITORS: &[&str] = &["vim", "vi", "nvim", "gvim"];
+
+// How the editor process shares the terminal with vuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchMode {
+    // Takes over the terminal until it exits
+    Inline,
+    // Runs on its own while vuit carries on
+    Detached,
+}
+
+// How several files open in one editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Buffers,
+    Tabs,
+    Splits,
+    Vsplits,
+}
+
+impl Layout {
+    pub fn next(self) -> Self {
+        match self {
+            Layout::Buffers => Layout::Tabs,
+            Layout::Tabs => Layout::Splits,
+            Layout::Splits => Layout::Vsplits,
+            Layout::Vsplits => Layout::Buffers,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Layout::Buffers => "buffers",
+            Layout::Tabs => "tabs",
+            Layout::Splits => "splits",
+            Layout::Vsplits => "vsplits",
+        }
+    }
+
+    fn flag(self) -> Option<&'static str> {
+        match self {
+            Layout::Buffers => None,
+            Layout::Tabs => Some("-p"),
+            Layout::Splits => Some("-o"),
+            Layout::Vsplits => Some("-O"),
+        }
+    }
+}
+
+// Launch templates of the editors vuit knows, keyed by executable name
+pub const PRESETS: &[(&str, &str)] = &[
+    ("vim", "{editor} '+call cursor({line}, {col})' {file}"),
+    ("vi", "{editor} +{line} {file}"),
+    ("nvim", "{editor} '+call cursor({line}, {col})' {file}"),
+    ("hx", "{editor} {file}:{line}:{col}"),
+    ("helix", "{editor} {file}:{line}:{col}"),
+    ("emacs", "{editor} +{line}:{col} {file}"),
+    ("emacsclient", "{editor} +{line}:{col} {file}"),
+    ("nano", "{editor} +{line},{col} {file}"),
+    ("kak", "{editor} +{line}:{col} {file}"),
+    ("code", "{editor} -g {file}:{line}:{col}"),
+    ("codium", "{editor} -g {file}:{line}:{col}"),
+];
+
+// Where in a file the editor should open, lines and columns start at 1
+#[derive(Clone, Copy, Default)]
+pub struct Position {
+    pub line: usize,
+    pub col: Option<usize>,
+}
+
+fn editor_name(editor: &str) -> &str {
+    Path::new(editor)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(editor)
+}
+
+// Configured mode, else detached for GUI editors and inline for the rest
+pub fn launch_mode(editor: &str, configured: Option<LaunchMode>) -> LaunchMode {
+    configured.unwrap_or_else(|| {
+        if GUI_EDITORS.contains(&editor_name(editor)) {
+            LaunchMode::Detached
+        } else {
+            LaunchMode::Inline
+        }
+    })
+}
+
+// Configured template, else the preset of the editor, else just the file
+pub fn template<'a>(editor: &str, configured: &'a str) -> &'a str {
+    if !configured.is_empty() {
+        return configured;
+    }
+
+    let name = editor_name(editor);
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, template)| *template)
+        .unwrap_or(DEFAULT_TEMPLATE)
+}
+
+// Program and arguments to open a file with, filled in from a template
+pub fn command(
+    template: &str,
+    editor: &str,
+    file: &str,
+    position: Option<Position>,
+) -> Vec<String> {
+    let Some(position) = position else {
+        return command_many(template, editor, &[file.to_string()], Layout::Buffers);
+    };
+
+    split_words(template)
+        .iter()
+        .map(|word| fill(word, editor, file, position))
+        .collect()
+}
+
+// Program and arguments to open several files at once. The words that only place
+// the cursor are dropped and the file word is repeated for every file
+pub fn command_many(template: &str, editor: &str, files: &[String], layout: Layout) -> Vec<String> {
+    let mut args = Vec::new();
+
+    for word in split_words(template) {
+        if let Some(start) = word.find("{file}") {
+            let end = start + "{file}".len();
+            args.extend(
+                files
+                    .iter()
+                    .map(|file| fill(&word[..end], editor, file, Position::default())),
+            );
+        } else if !word.contains("{line}") && !word.contains("{col}") {
+            args.push(fill(&word, editor, "", Position::default()));
+            // The layout flag goes right after the program
+            if args.len() == 1
+                && VIM_EDITORS.contains(&editor_name(editor))
+                && let Some(flag) = layout.flag()
+            {
+                args.push(flag.to_string());
+            }
+        }
+    }
+
+    args
+}
+
+// Program and arguments to walk an errorfile. vim-like editors load it as their quickfix
+// list, emacs visits it in grep-mode and the rest are given the files it names
+pub fn quickfix_command(
+    template: &str,
+    editor: &str,
+    errorfile: &str,
+    files: &[String],
+) -> Vec<String> {
+    let name = editor_name(editor);
+    if VIM_EDITORS.contains(&name) {
+        let mut args = command_many(template, editor, &[], Layout::Buffers);
+        args.extend(["-q".to_string(), errorfile.to_string()]);
+        args
+    } else if name == "emacs" {
+        let mut args = command_many(template, editor, &[errorfile.to_string()], Layout::Buffers);
+        args.extend(["-f".to_string(), "grep-mode".to_string()]);
+        args
+    } else {
+        command_many(template, editor, files, Layout::Buffers)
+    }
+}
+
+fn fill(word: &str, editor: &str, file: &str, position: Position) -> String {
+    word.replace("{editor}", editor)
+        .replace("{file}", file)
+        .replace("{line}", &position.line.max(1).to_string())
+        .replace("{col}", &position.col.unwrap_or(1).max(1).to_string())
+}
+
+// Splits on whitespace outside of single or double quotes, dropping the quotes
+fn split_words(template: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+// Hands the terminal to the editor and takes it back once the editor exits
+pub fn run_inline(terminal: &mut DefaultTerminal, args: &[String]) -> io::Result<()> {
+    let (program, rest) = args.split_first().ok_or(io::ErrorKind::InvalidInput)?;
+
+    execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen)?;
+    disable_raw_mode()?;
+
+    let status = Command::new(program).args(rest).status();
+
+    // Restore vuit's screen whether or not the editor ran
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    // Everything ratatui drew before is gone, so the next frame redraws it all
+    terminal.clear()?;
+
+    status.map(|_| ())
+}
+
+// Starts the editor in its own process group, away from vuit's terminal
+pub fn run_detached(args: &[String]) -> io::Result<()> {
+    let (program, rest) = args.split_first().ok_or(io::ErrorKind::InvalidInput)?;
+
+    let mut child = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+
+    // Reap it when it exits so it does not linger as a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, col: Option<usize>) -> Option<Position> {
+        Some(Position { line, col })
+    }
+
+    #[test]
+    fn quoted_words_stay_whole() {
+        assert_eq!(
+            split_words(r#"{editor}  '+call cursor({line}, {col})' "my file"  -x"#),
+            ["{editor}", "+call cursor({line}, {col})", "my file", "-x"]
+        );
+        assert_eq!(split_words("a '' b"), ["a", "", "b"]);
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn presets_fill_line_and_column() {
+        let vim = template("/usr/bin/nvim", "");
+        assert_eq!(
+            command(vim, "/usr/bin/nvim", "src/main.rs", at(12, Some(5))),
+            ["/usr/bin/nvim", "+call cursor(12, 5)", "src/main.rs"]
+        );
+        assert_eq!(
+            command(template("hx", ""), "hx", "a b.rs", at(3, None)),
+            ["hx", "a b.rs:3:1"]
+        );
+        assert_eq!(
+            command(template("ed", ""), "ed", "notes", at(3, Some(2))),
+            ["ed", "notes"]
+        );
+    }
+
+    #[test]
+    fn configured_template_wins_over_the_preset() {
+        let custom = "{editor} --goto {file}:{line}";
+        assert_eq!(template("vim", custom), custom);
+        assert_eq!(
+            command(custom, "vim", "x.rs", at(0, None)),
+            ["vim", "--goto", "x.rs:1"]
+        );
+    }
+
+    #[test]
+    fn without_a_position_the_cursor_words_are_dropped() {
+        assert_eq!(
+            command(template("vim", ""), "vim", "x.rs", None),
+            ["vim", "x.rs"]
+        );
+        assert_eq!(
+            command(template("code", ""), "code", "x.rs", None),
+            ["code", "-g", "x.rs"]
+        );
+    }
+
+    #[test]
+    fn gui_editors_launch_detached() {
+        assert_eq!(launch_mode("/opt/bin/code", None), LaunchMode::Detached);
+        assert_eq!(launch_mode("vim", None), LaunchMode::Inline);
+        assert_eq!(
+            launch_mode("code", Some(LaunchMode::Inline)),
+            LaunchMode::Inline
+        );
+    }
+
+    #[test]
+    fn many_files_repeat_the_file_word_without_the_cursor() {
+        let files = ["a.rs".to_string(), "b c.rs".to_string()];
+        assert_eq!(
+            command_many(template("hx", ""), "hx", &files, Layout::Tabs),
+            ["hx", "a.rs", "b c.rs"]
+        );
+        assert_eq!(
+            command_many(template("emacs", ""), "emacs", &files, Layout::Buffers),
+            ["emacs", "a.rs", "b c.rs"]
+        );
+    }
+
+    #[test]
+    fn vim_editors_get_the_layout_flag_after_the_program() {
+        let files = ["a.rs".to_string(), "b.rs".to_string()];
+        let vim = template("vim", "");
+        assert_eq!(
+            command_many(vim, "vim", &files, Layout::Buffers),
+            ["vim", "a.rs", "b.rs"]
+        );
+        assert_eq!(
+            command_many(vim, "/usr/bin/nvim", &files, Layout::Tabs),
+            ["/usr/bin/nvim", "-p", "a.rs", "b.rs"]
+        );
+        assert_eq!(
+            command_many("{editor} -n {file}", "vim", &files, Layout::Vsplits),
+            ["vim", "-O", "-n", "a.rs", "b.rs"]
+        );
+    }
+}
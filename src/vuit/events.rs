@@ -1,51 +1,67 @@
 use crate::vuit::{Context, Vuit};
 use crossterm::event::{self, Event, KeyEventKind};
 use ratatui::DefaultTerminal;
-use std::sync::atomic::Ordering;
 
-use crate::vuit::contexts::{fileviewer, stringsearch, stringsearchreplace, terminal};
+use crate::vuit::contexts::{
+    fileviewer, replacereview, stringsearch, stringsearchreplace, tasks, terminal,
+};
 use crossterm::event::KeyCode;
 
 pub fn dispatch_event(app: &mut Vuit, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+    // Stream in any string search results found since the last frame
+    app.collect_search_results();
+    // Pick up the exit status of finished tasks
+    terminal::poll_tasks(app);
+    // The wheel scrolls the Terminal, elsewhere the mouse is left to the host terminal
+    app.sync_mouse_capture();
+    // Marks never carry over to another context's list
+    app.sync_marks();
+
     if !event::poll(std::time::Duration::from_millis(100))? {
-        if app.search_in_progress
-            && app.search_progress.load(Ordering::Relaxed) == app.file_list.len()
-        {
-            if let Ok(mut result) = app.search_result.lock() {
-                if let Some(data) = result.take() {
-                    app.file_str_list = data;
-                    app.search_in_progress = false;
-                }
-            }
-        }
         return Ok(());
     }
 
-    if let Event::Key(key_event) = event::read()? {
-        if key_event.kind != KeyEventKind::Press {
-            return Ok(());
-        }
-
-        match app.switch_context {
-            Context::Fileviewer => {
-                fileviewer::handler(app, key_event, terminal);
-            }
-            Context::Stringsearch => {
-                stringsearch::handler(app, key_event, terminal);
+    match event::read()? {
+        Event::Key(key_event) => {
+            if key_event.kind != KeyEventKind::Press {
+                return Ok(());
             }
-            Context::Stringsearchreplace => {
-                stringsearchreplace::handler(app, key_event, terminal);
-            }
-            Context::Terminal => {
-                terminal::handler(app, key_event, terminal);
+
+            match app.switch_context {
+                Context::Fileviewer => {
+                    fileviewer::handler(app, key_event, terminal);
+                }
+                Context::Stringsearch => {
+                    stringsearch::handler(app, key_event, terminal);
+                }
+                Context::Stringsearchreplace => {
+                    stringsearchreplace::handler(app, key_event, terminal);
+                }
+                Context::Replacereview => {
+                    replacereview::handler(app, key_event, terminal);
+                }
+                Context::Terminal => {
+                    terminal::handler(app, key_event, terminal);
+                }
+                Context::Tasks => {
+                    tasks::handler(app, key_event, terminal);
+                }
+                Context::Help => {
+                    fileviewer::handler(app, key_event, terminal);
+                }
             }
-            Context::Help => {
-                fileviewer::handler(app, key_event, terminal);
+            // Enter also starts searches, reviews and tasks, only an opened file ends a oneshot
+            if app.oneshot && key_event.code == KeyCode::Enter && app.editor_opened {
+                app.exit = true;
             }
         }
-        if app.oneshot && key_event.code == KeyCode::Enter {
-            app.exit = true;
+        Event::Mouse(mouse) if app.switch_context == Context::Terminal => {
+            terminal::mouse_handler(app, mouse);
+        }
+        Event::Resize(cols, rows) => {
+            terminal::resize_to_window(app, cols, rows);
         }
+        _ => {}
     }
 
     Ok(())
@@ -0,0 +1,78 @@
+use crate::vuit::utils::project_dir;
+use std::{fs, io, path::PathBuf};
+
+// Constants
+const HISTORY_DIR: &str = "~/.vuit/term_history";
+const HISTORY_LIMIT: usize = 1000;
+
+// One history file per project
+fn history_path() -> io::Result<PathBuf> {
+    project_dir(HISTORY_DIR)
+}
+
+// Terminal commands run in this project, oldest first
+pub fn load() -> Vec<String> {
+    history_path()
+        .and_then(fs::read_to_string)
+        .map(|content| content.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+// Moves a command to the end of a history, dropping its older copy and the oldest
+// commands beyond the limit
+pub fn push_unique(history: &mut Vec<String>, command: &str) {
+    history.retain(|entry| entry != command);
+    history.push(command.to_string());
+    let excess = history.len().saturating_sub(HISTORY_LIMIT);
+    history.drain(..excess);
+}
+
+// Adds a command to the shared history, re-reading it so other sessions are kept
+pub fn record(command: &str) -> io::Result<()> {
+    let path = history_path()?;
+    let mut history = load();
+    push_unique(&mut history, command);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = history.join("\n");
+    content.push('\n');
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_commands_move_to_the_end() {
+        let mut history = vec![
+            "ls".to_string(),
+            "make".to_string(),
+            "git status".to_string(),
+        ];
+        push_unique(&mut history, "make");
+        assert_eq!(history, ["ls", "git status", "make"]);
+    }
+
+    #[test]
+    fn oldest_commands_are_trimmed_at_the_limit() {
+        let mut history: Vec<String> = (0..HISTORY_LIMIT).map(|i| i.to_string()).collect();
+        push_unique(&mut history, "new");
+
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history.first().unwrap(), "1");
+        assert_eq!(history.last().unwrap(), "new");
+    }
+
+    #[test]
+    fn a_repeat_at_the_limit_drops_nothing_else() {
+        let mut history: Vec<String> = (0..HISTORY_LIMIT).map(|i| i.to_string()).collect();
+        push_unique(&mut history, "0");
+
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history.first().unwrap(), "1");
+        assert_eq!(history.last().unwrap(), "0");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::vuit::rewrite::write_atomic;
+use crate::vuit::utils::{content_hash, project_dir};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+// Constants
+const JOURNAL_DIR: &str = "~/.vuit/replace_journal";
+const MANIFEST_FILE: &str = "journal.json";
+
+// Original contents of a file touched by the last replace
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    path: PathBuf,
+    backup: String,
+    written_hash: u64,
+}
+
+// Outcome of undoing the last replace
+#[derive(Default)]
+pub struct UndoReport {
+    pub restored: Vec<String>,
+    pub modified: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+// A file about to be rewritten by a replace
+pub struct JournalWrite<'a> {
+    pub path: &'a Path,
+    pub original: &'a [u8],
+    pub written: &'a [u8],
+}
+
+// Replaces the project's journal with the files of the latest replace, no files clears it
+pub fn record(writes: &[JournalWrite]) -> io::Result<()> {
+    record_in(&project_dir(JOURNAL_DIR)?, writes)
+}
+
+// Restores every journaled file of the project that is unchanged since the replace
+pub fn undo_last() -> io::Result<UndoReport> {
+    undo_in(&project_dir(JOURNAL_DIR)?)
+}
+
+fn record_in(dir: &Path, writes: &[JournalWrite]) -> io::Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    if writes.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+
+    let cwd = env::current_dir()?;
+    let mut entries = Vec::new();
+
+    for (index, write) in writes.iter().enumerate() {
+        let backup = format!("{}.orig", index);
+        fs::write(dir.join(&backup), write.original)?;
+        entries.push(JournalEntry {
+            path: cwd.join(write.path),
+            backup,
+            written_hash: content_hash(write.written),
+        });
+    }
+
+    write_manifest(dir, &entries)
+}
+
+fn undo_in(dir: &Path) -> io::Result<UndoReport> {
+    let manifest = fs::read_to_string(dir.join(MANIFEST_FILE))?;
+    let entries: Vec<JournalEntry> = serde_json::from_str(&manifest).map_err(io::Error::other)?;
+
+    let mut report = UndoReport::default();
+    let mut remaining = Vec::new();
+
+    for entry in entries {
+        let current = fs::read(&entry.path).ok();
+        if current.as_deref().map(content_hash) != Some(entry.written_hash) {
+            report.modified.push(display_path(&entry.path));
+            remaining.push(entry);
+            continue;
+        }
+
+        let restored = fs::read(dir.join(&entry.backup))
+            .and_then(|original| write_atomic(&entry.path, &original));
+        match restored {
+            Ok(()) => {
+                let _ = fs::remove_file(dir.join(&entry.backup));
+                report.restored.push(display_path(&entry.path));
+            }
+            Err(_) => {
+                report.failed.push(display_path(&entry.path));
+                remaining.push(entry);
+            }
+        }
+    }
+
+    // Files that were refused or failed stay journaled so they can be retried, the
+    // restored ones are gone from the manifest
+    if remaining.is_empty() {
+        fs::remove_dir_all(dir)?;
+    } else {
+        write_manifest(dir, &remaining)?;
+    }
+
+    Ok(report)
+}
+
+fn write_manifest(dir: &Path, entries: &[JournalEntry]) -> io::Result<()> {
+    let manifest = serde_json::to_string_pretty(entries).map_err(io::Error::other)?;
+    fs::write(dir.join(MANIFEST_FILE), manifest)
+}
+
+fn display_path(path: &Path) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(path: &Path, original: &[u8], written: &[u8], journal: &Path) {
+        fs::write(path, written).unwrap();
+        let write = JournalWrite {
+            path,
+            original,
+            written,
+        };
+        record_in(journal, &[write]).unwrap();
+    }
+
+    #[test]
+    fn undo_restores_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal");
+        let file = dir.path().join("a.rs");
+        replace(&file, b"old\n", b"new\n", &journal);
+
+        let report = undo_in(&journal).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert_eq!(fs::read(&file).unwrap(), b"old\n");
+        // Nothing is left to undo
+        assert!(!journal.exists());
+        assert!(undo_in(&journal).is_err_and(|e| e.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn undo_refuses_files_edited_since_the_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal");
+        let file = dir.path().join("a.rs");
+        replace(&file, b"old\n", b"new\n", &journal);
+        fs::write(&file, b"new\nedited\n").unwrap();
+
+        let report = undo_in(&journal).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.modified.len(), 1);
+        assert_eq!(fs::read(&file).unwrap(), b"new\nedited\n");
+
+        // Still journaled, so it can be undone once the edit is reverted
+        fs::write(&file, b"new\n").unwrap();
+        assert_eq!(undo_in(&journal).unwrap().restored.len(), 1);
+        assert_eq!(fs::read(&file).unwrap(), b"old\n");
+    }
+
+    #[test]
+    fn failed_restores_stay_journaled_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal");
+        let kept = dir.path().join("a.rs");
+        let gone = dir.path().join("b.rs");
+        fs::write(&kept, b"new").unwrap();
+        fs::write(&gone, b"new").unwrap();
+        let writes = [&kept, &gone].map(|path| JournalWrite {
+            path,
+            original: b"old",
+            written: b"new",
+        });
+        record_in(&journal, &writes).unwrap();
+
+        // The second backup is lost, so only the first file can be restored
+        let backup = journal.join("1.orig");
+        fs::remove_file(&backup).unwrap();
+
+        let report = undo_in(&journal).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        let manifest = fs::read_to_string(journal.join(MANIFEST_FILE)).unwrap();
+        let entries: Vec<JournalEntry> = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, gone);
+    }
+}
@@ -0,0 +1,166 @@
+use regex::Regex;
+use std::path::PathBuf;
+
+// Built-in patterns, each with file and line groups and an optional col group
+pub const PRESETS: &[(&str, &str)] = &[
+    (
+        "rustc",
+        r"-->\s+(?P<file>[^\s:]+):(?P<line>\d+):(?P<col>\d+)",
+    ),
+    (
+        "gcc",
+        r"^(?P<file>[^\s:]+):(?P<line>\d+):(?P<col>\d+): (?:fatal error|error|warning|note)",
+    ),
+    (
+        "tsc",
+        r"^(?P<file>[^\s(:]+)\((?P<line>\d+),(?P<col>\d+)\): (?:error|warning)",
+    ),
+    (
+        "tsc",
+        r"^(?P<file>[^\s:]+):(?P<line>\d+):(?P<col>\d+) - (?:error|warning)",
+    ),
+    ("python", r#"File "(?P<file>[^"]+)", line (?P<line>\d+)"#),
+];
+
+// A file position found in terminal output
+pub struct Location {
+    pub output_line: usize,
+    pub file: String,
+    pub line_number: usize,
+    pub column: Option<usize>,
+}
+
+// Compiles the enabled presets and the user's own patterns, collecting bad patterns
+pub fn build_patterns(presets: &[String], custom: &[String]) -> (Vec<Regex>, Vec<String>) {
+    let mut patterns = Vec::new();
+    let mut invalid = Vec::new();
+
+    for (name, pattern) in PRESETS {
+        if presets.iter().any(|preset| preset == name) {
+            patterns.push(Regex::new(pattern).expect("Invalid preset pattern"));
+        }
+    }
+    for pattern in custom {
+        match Regex::new(pattern) {
+            Ok(regex) if has_group(&regex, "file") && has_group(&regex, "line") => {
+                patterns.push(regex)
+            }
+            _ => invalid.push(pattern.clone()),
+        }
+    }
+
+    (patterns, invalid)
+}
+
+fn has_group(regex: &Regex, group: &str) -> bool {
+    regex.capture_names().flatten().any(|name| name == group)
+}
+
+// First location on each output line that points at a file that exists, relative paths are
+// tried in each directory in turn and kept as found there
+pub fn find_locations(lines: &[String], patterns: &[Regex], dirs: &[PathBuf]) -> Vec<Location> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(output_line, text)| {
+            patterns.iter().find_map(|pattern| {
+                let caps = pattern.captures(text)?;
+                let file = caps.name("file")?.as_str();
+                let path = dirs
+                    .iter()
+                    .map(|dir| dir.join(file))
+                    .find(|path| path.is_file())?;
+                Some(Location {
+                    output_line,
+                    file: path.to_str()?.to_string(),
+                    line_number: caps.name("line")?.as_str().parse().ok()?,
+                    column: caps.name("col").and_then(|col| col.as_str().parse().ok()),
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn all_presets() -> Vec<Regex> {
+        let names: Vec<String> = PRESETS.iter().map(|(name, _)| name.to_string()).collect();
+        build_patterns(&names, &[]).0
+    }
+
+    fn position(text: &str) -> Option<(String, usize, Option<usize>)> {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let dirs = [dir.path().to_path_buf()];
+
+        find_locations(&[text.to_string()], &all_presets(), &dirs)
+            .pop()
+            .map(|loc| {
+                let file = loc.file.strip_prefix(dir.path().to_str().unwrap()).unwrap();
+                (file.to_string(), loc.line_number, loc.column)
+            })
+    }
+
+    #[test]
+    fn presets_read_each_tool_format() {
+        let expected = Some(("/src/main.rs".to_string(), 12, Some(5)));
+        assert_eq!(position("  --> src/main.rs:12:5"), expected);
+        assert_eq!(position("src/main.rs:12:5: error: expected ';'"), expected);
+        assert_eq!(
+            position("src/main.rs(12,5): error TS2304: Cannot find name"),
+            expected
+        );
+        assert_eq!(
+            position("src/main.rs:12:5 - error TS2304: Cannot find name"),
+            expected
+        );
+        assert_eq!(
+            position(r#"  File "src/main.rs", line 12, in <module>"#),
+            Some(("/src/main.rs".to_string(), 12, None))
+        );
+    }
+
+    #[test]
+    fn lines_without_an_existing_file_are_skipped() {
+        assert_eq!(position("  --> src/missing.rs:1:1"), None);
+        assert_eq!(position("src/main.rs:12:5: some other text"), None);
+    }
+
+    #[test]
+    fn files_are_tried_in_each_directory() {
+        let shell_dir = tempfile::tempdir().unwrap();
+        let other_dir = tempfile::tempdir().unwrap();
+        fs::write(other_dir.path().join("lib.rs"), "").unwrap();
+        let dirs = [
+            shell_dir.path().to_path_buf(),
+            other_dir.path().to_path_buf(),
+        ];
+
+        let found = find_locations(
+            &["lib.rs:3:1: warning: unused".to_string()],
+            &all_presets(),
+            &dirs,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].file,
+            other_dir.path().join("lib.rs").to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_patterns_need_file_and_line_groups() {
+        let custom = [
+            r"^(?P<file>\S+) line (?P<line>\d+)".to_string(),
+            r"^(\S+):(\d+)".to_string(),
+            r"^(?P<file>\S+):(\d+)".to_string(),
+        ];
+        let (patterns, invalid) = build_patterns(&[], &custom);
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(invalid, custom[1..]);
+    }
+}
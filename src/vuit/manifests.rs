@@ -0,0 +1,358 @@
+use crate::vuit::utils::project_dir;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs, io, path::Path};
+
+// Constants
+const USER_TASKS_FILE: &str = ".vuit/tasks.json";
+const LAST_TASK_DIR: &str = "~/.vuit/last_task";
+const SOURCES: [&str; 4] = ["vuit", "cargo", "make", "npm"];
+
+// A runnable target found in the project
+#[derive(Clone)]
+pub struct Task {
+    pub source: &'static str,
+    pub name: String,
+    pub command: String,
+}
+
+// Last task run in a project, kept across restarts
+#[derive(Serialize, Deserialize)]
+struct SavedTask {
+    source: String,
+    name: String,
+    command: String,
+}
+
+// Entry of the user's own .vuit/tasks.json
+#[derive(Deserialize)]
+struct UserTask {
+    name: String,
+    command: String,
+}
+
+// Every task of the project, the user's own first
+pub fn discover() -> Vec<Task> {
+    discover_in(Path::new("."))
+}
+
+// Task last run in this project, if any
+pub fn load_last() -> Option<Task> {
+    load_last_from(&project_dir(LAST_TASK_DIR).ok()?)
+}
+
+fn load_last_from(path: &Path) -> Option<Task> {
+    let content = fs::read_to_string(path).ok()?;
+    let saved: SavedTask = serde_json::from_str(&content).ok()?;
+    let source = SOURCES.into_iter().find(|source| *source == saved.source)?;
+    Some(Task {
+        source,
+        name: saved.name,
+        command: saved.command,
+    })
+}
+
+// Remembers the task for <C-l> after vuit restarts
+pub fn save_last(task: &Task) -> io::Result<()> {
+    save_last_to(&project_dir(LAST_TASK_DIR)?, task)
+}
+
+fn save_last_to(path: &Path, task: &Task) -> io::Result<()> {
+    let saved = SavedTask {
+        source: task.source.to_string(),
+        name: task.name.clone(),
+        command: task.command.clone(),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(&saved)?)
+}
+
+fn discover_in(root: &Path) -> Vec<Task> {
+    let mut tasks = user_tasks(root);
+    tasks.extend(cargo_tasks(root));
+    tasks.extend(make_tasks(root));
+    tasks.extend(npm_tasks(root));
+    tasks
+}
+
+fn user_tasks(root: &Path) -> Vec<Task> {
+    let Ok(content) = fs::read_to_string(root.join(USER_TASKS_FILE)) else {
+        return Vec::new();
+    };
+
+    serde_json::from_str::<Vec<UserTask>>(&content)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|task| Task {
+            source: "vuit",
+            name: task.name,
+            command: task.command,
+        })
+        .collect()
+}
+
+// Bins, examples and tests, declared in Cargo.toml or found where cargo looks for them
+fn cargo_tasks(root: &Path) -> Vec<Task> {
+    let Ok(content) = fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    let declared = |kind: &str| -> BTreeSet<String> {
+        manifest
+            .get(kind)
+            .and_then(|targets| targets.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|target| target.get("name")?.as_str().map(String::from))
+            .collect()
+    };
+
+    // src/main.rs is the package's own bin unless a [[bin]] already claims it
+    let main_declared = manifest
+        .get("bin")
+        .and_then(|targets| targets.as_array())
+        .into_iter()
+        .flatten()
+        .any(|target| target.get("path").and_then(|path| path.as_str()) == Some("src/main.rs"));
+
+    let mut bins = declared("bin");
+    if let Some(name) = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        && root.join("src/main.rs").is_file()
+        && !main_declared
+    {
+        bins.insert(name.to_string());
+    }
+    bins.extend(rust_targets(&root.join("src/bin")));
+
+    let mut examples = declared("example");
+    examples.extend(rust_targets(&root.join("examples")));
+
+    let mut tests = declared("test");
+    tests.extend(rust_targets(&root.join("tests")));
+
+    let task = |name: &String, command: String| Task {
+        source: "cargo",
+        name: name.clone(),
+        command,
+    };
+
+    bins.iter()
+        .map(|name| task(name, format!("cargo run --bin {}", name)))
+        .chain(
+            examples
+                .iter()
+                .map(|name| task(name, format!("cargo run --example {}", name))),
+        )
+        .chain(
+            tests
+                .iter()
+                .map(|name| task(name, format!("cargo test --test {}", name))),
+        )
+        .collect()
+}
+
+// Target names cargo discovers in a directory, name.rs files and name/main.rs dirs
+fn rust_targets(dir: &Path) -> BTreeSet<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return BTreeSet::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let is_target = (path.is_file() && path.extension().is_some_and(|ext| ext == "rs"))
+                || path.join("main.rs").is_file();
+            if is_target {
+                path.file_stem()?.to_str().map(String::from)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Explicit Makefile targets, skipping special and pattern rules
+fn make_tasks(root: &Path) -> Vec<Task> {
+    let Ok(content) = fs::read_to_string(root.join("Makefile")) else {
+        return Vec::new();
+    };
+
+    let mut seen = BTreeSet::new();
+    let mut tasks = Vec::new();
+
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((targets, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // Variable assignments use := and ::=
+        if rest.starts_with('=') || rest.starts_with(":=") || targets.contains('=') {
+            continue;
+        }
+
+        for target in targets.split_whitespace() {
+            if target.contains(['%', '$']) || !seen.insert(target.to_string()) {
+                continue;
+            }
+            tasks.push(Task {
+                source: "make",
+                name: target.to_string(),
+                command: format!("make {}", target),
+            });
+        }
+    }
+
+    tasks
+}
+
+fn npm_tasks(root: &Path) -> Vec<Task> {
+    let Ok(content) = fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+
+    package
+        .get("scripts")
+        .and_then(|scripts| scripts.as_object())
+        .into_iter()
+        .flatten()
+        .map(|(name, _)| Task {
+            source: "npm",
+            name: name.clone(),
+            command: format!("npm run {}", name),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn commands(tasks: Vec<Task>) -> Vec<String> {
+        tasks.into_iter().map(|task| task.command).collect()
+    }
+
+    #[test]
+    fn the_last_task_survives_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("last_task").join("project");
+        assert!(load_last_from(&path).is_none());
+
+        let task = Task {
+            source: "make",
+            name: "check".to_string(),
+            command: "make check".to_string(),
+        };
+        save_last_to(&path, &task).unwrap();
+
+        let last = load_last_from(&path).unwrap();
+        assert_eq!(last.source, "make");
+        assert_eq!(last.name, "check");
+        assert_eq!(last.command, "make check");
+    }
+
+    #[test]
+    fn cargo_targets_are_declared_or_discovered() {
+        let dir = project(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\n\n[[bin]]\nname = \"tool\"\npath = \"src/tool.rs\"\n\n[[test]]\nname = \"smoke\"\n",
+            ),
+            ("src/main.rs", ""),
+            ("src/bin/extra.rs", ""),
+            ("examples/demo/main.rs", ""),
+            ("examples/notes.txt", ""),
+            ("tests/api.rs", ""),
+        ]);
+
+        assert_eq!(
+            commands(cargo_tasks(dir.path())),
+            [
+                "cargo run --bin app",
+                "cargo run --bin extra",
+                "cargo run --bin tool",
+                "cargo run --example demo",
+                "cargo test --test api",
+                "cargo test --test smoke",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_bin_claiming_main_replaces_the_package_bin() {
+        let dir = project(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\n\n[[bin]]\nname = \"cli\"\npath = \"src/main.rs\"\n",
+            ),
+            ("src/main.rs", ""),
+        ]);
+
+        assert_eq!(commands(cargo_tasks(dir.path())), ["cargo run --bin cli"]);
+    }
+
+    #[test]
+    fn make_skips_variables_special_and_pattern_rules() {
+        let dir = project(&[(
+            "Makefile",
+            "CC := gcc\nFLAGS = -O2\nOUT ::= bin\n.PHONY: all clean\nall build: main.o\n\tgcc main.o\n%.o: %.c\n$(OUT): all\n# lint: old\nclean:\nall:\n",
+        )]);
+
+        assert_eq!(
+            commands(make_tasks(dir.path())),
+            ["make all", "make build", "make clean"]
+        );
+    }
+
+    #[test]
+    fn user_tasks_come_first() {
+        let dir = project(&[
+            (
+                ".vuit/tasks.json",
+                r#"[{"name": "serve", "command": "python -m http.server"}]"#,
+            ),
+            (
+                "package.json",
+                r#"{"scripts": {"test": "jest", "build": "tsc"}}"#,
+            ),
+        ]);
+
+        assert_eq!(
+            commands(discover_in(dir.path())),
+            ["python -m http.server", "npm run build", "npm run test"]
+        );
+    }
+
+    #[test]
+    fn broken_manifests_give_no_tasks() {
+        let dir = project(&[
+            (".vuit/tasks.json", "[{"),
+            ("Cargo.toml", "[package"),
+            ("package.json", "{"),
+        ]);
+
+        assert!(discover_in(dir.path()).is_empty());
+    }
+}
@@ -1,28 +1,52 @@
 // Modules
+pub mod editor;
 pub mod events;
+pub mod history;
+pub mod journal;
+pub mod locations;
+pub mod manifests;
+pub mod multiplexer;
+pub mod nvim;
+pub mod quickfix;
+pub mod rewrite;
+pub mod search;
+pub mod termscreen;
 pub mod ui;
 pub mod utils;
 
 pub mod contexts {
     pub mod fileviewer;
+    pub mod replacereview;
     pub mod stringsearch;
     pub mod stringsearchreplace;
+    pub mod tasks;
     pub mod terminal;
 }
 
 // Vuit Imports
-use crate::vuit::contexts::terminal::start_term;
+use crate::vuit::contexts::terminal::{completion_candidates, send_cmd_to_proc_term, start_term};
+use crate::vuit::editor::{LaunchMode, Layout, Position};
 use crate::vuit::events::dispatch_event;
+use crate::vuit::journal::JournalWrite;
+use crate::vuit::locations::PRESETS;
+use crate::vuit::manifests::Task;
+use crate::vuit::multiplexer::{Backend, Multiplexer, Placement};
+use crate::vuit::rewrite::{FileStamp, rewrite_lines, split_lines, strip_terminator, write_atomic};
+use crate::vuit::search::{
+    SearchOptions, build_matcher, match_ranges, replace_line, replace_matches,
+};
+use crate::vuit::termscreen::TermScreen;
 use crate::vuit::ui::dispatch_render;
-use crate::vuit::utils::{clean_utf8_content, expand_tilde};
+use crate::vuit::utils::{clean_utf8_content, expand_tilde, split_search_result};
 use clap::Arg;
 use std::error::Error;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
 
 // Std Lib
 use std::{
-    collections::HashMap,
-    fs::{self, File, read_to_string, write},
+    collections::{HashMap, HashSet},
+    fs::{self, File},
     io::{self, BufRead, BufReader, Write},
     path::Path,
     sync::{Arc, Mutex},
@@ -34,13 +58,16 @@ use ratatui::{DefaultTerminal, widgets::ListState};
 
 // External Crates
 use clap::Command as ClapCommand;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use grep::searcher::{BinaryDetection, SearcherBuilder, sinks::Lossy};
 use ignore::{DirEntry, WalkBuilder};
+use indexmap::IndexSet;
 use itertools::Itertools;
-use memchr::memmem;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 // Constants
 const RECENT_BUFFERS_NUM_LINES: u16 = 8;
@@ -65,17 +92,89 @@ enum Context {
     Fileviewer,
     Stringsearch,
     Stringsearchreplace,
+    Replacereview,
     Terminal,
+    Tasks,
     Help,
 }
 
+// Pending Search and Replace Change
+pub struct ReplaceEdit {
+    file: String,
+    line_number: usize,
+    // Byte offset of the match in the line, each match of a line is its own edit
+    start: usize,
+    before: String,
+    after: String,
+    accepted: bool,
+}
+
+// Embedded Terminal Session
+pub struct TermSession {
+    name: String,
+    process: Box<dyn portable_pty::Child + Send + Sync>,
+    master: Box<dyn portable_pty::MasterPty + Send>,
+    screen: Arc<Mutex<TermScreen>>,
+    writer: Box<dyn Write + Send>,
+    history: Vec<String>,
+    task: Option<TaskRun>,
+}
+
+// Task Running in a Terminal Session
+pub struct TaskRun {
+    started: Instant,
+    finished: Option<(String, Duration)>,
+}
+
 // Vuit Configuration
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VuitRC {
     colorscheme: String,
     highlight_color: String,
     editor: String,
+    #[serde(default)]
+    editor_template: String,
+    #[serde(default)]
+    editor_mode: Option<LaunchMode>,
+    #[serde(default)]
+    open_layout: Layout,
+    #[serde(default)]
+    nvim_socket: String,
+    #[serde(default)]
+    multiplexer: Option<Backend>,
+    #[serde(default)]
+    pane_placement: Placement,
+    #[serde(default = "default_pane_size")]
+    pane_size: u16,
     oneshot: bool,
+    #[serde(default = "default_shell")]
+    shell: String,
+    #[serde(default)]
+    shell_args: Vec<String>,
+    #[serde(default)]
+    shell_env: HashMap<String, String>,
+    #[serde(default)]
+    shell_init: String,
+    #[serde(default = "default_error_presets")]
+    error_presets: Vec<String>,
+    #[serde(default)]
+    error_patterns: Vec<String>,
+}
+
+fn default_shell() -> String {
+    "bash".to_string()
+}
+
+fn default_pane_size() -> u16 {
+    80
+}
+
+fn default_error_presets() -> Vec<String> {
+    PRESETS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .dedup()
+        .collect()
 }
 
 impl Default for VuitRC {
@@ -84,7 +183,20 @@ impl Default for VuitRC {
             colorscheme: "white".to_string(),
             highlight_color: "lightblue".to_string(),
             editor: "vim".to_string(),
+            editor_template: String::new(),
+            editor_mode: None,
+            open_layout: Layout::default(),
+            nvim_socket: String::new(),
+            multiplexer: None,
+            pane_placement: Placement::default(),
+            pane_size: default_pane_size(),
             oneshot: false,
+            shell: default_shell(),
+            shell_args: Vec::new(),
+            shell_env: HashMap::new(),
+            shell_init: String::new(),
+            error_presets: default_error_presets(),
+            error_patterns: Vec::new(),
         }
     }
 }
@@ -96,6 +208,9 @@ pub struct Vuit {
     config: VuitRC,
     colorscheme_index: usize,
     oneshot: bool,
+    errorfile: Option<String>,
+    // Last errorfile handed to the editor, deleted when dropped
+    quickfix_file: Option<NamedTempFile>,
 
     // Input
     typed_input: String,
@@ -106,21 +221,62 @@ pub struct Vuit {
     preview: Vec<String>,
     recent_files: Vec<String>,
     fd_list: Vec<String>,
-    term_out: String,
     help_menu: Vec<String>,
     current_filter: String,
     current_str_filter: String,
     search_progress_str: String,
+    status_msg: String,
 
     // Terminal vars
-    bash_process: Option<Box<dyn portable_pty::Child + Send + Sync>>,
-    process_out: Arc<Mutex<Vec<String>>>,
-    command_sender: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+    term_sessions: Vec<TermSession>,
+    term_active: usize,
+    pty_size: (u16, u16),
+    mouse_captured: bool,
+    term_passthrough: bool,
+    term_search_active: bool,
+    term_search: String,
+    term_search_line: Option<usize>,
+    term_msg: String,
+    // Commands of this project shared by every session, read when <C-r> opens
+    term_history: Vec<String>,
+    term_history_index: Option<usize>,
+    term_history_draft: String,
+    term_history_search: Option<String>,
+    term_history_matches: Vec<String>,
+    term_history_pick: usize,
+    // Sorted files and directories of the last scan, for completion
+    term_candidates: Vec<String>,
+    term_completions: Vec<String>,
+    term_completion_pick: usize,
+    term_error_line: Option<usize>,
+    // Scrollback last opened with :edit, deleted when dropped
+    scrollback_file: Option<NamedTempFile>,
+
+    // Task Runner vars
+    tasks: Vec<Task>,
+    task_matches: Vec<usize>,
+    task_index: usize,
+    task_state: ListState,
+    last_task: Option<Task>,
 
     // String Search vars
+    search_opts: SearchOptions,
+    // Query and options the results were found with, none for a loaded errorfile
+    results_query: Option<(String, SearchOptions)>,
     search_in_progress: bool,
+    search_total: usize,
     search_progress: Arc<AtomicUsize>,
-    search_result: Arc<Mutex<Option<Vec<String>>>>,
+    search_cancel: Arc<AtomicBool>,
+    search_result: Arc<Mutex<Vec<String>>>,
+    search_stamps: Arc<Mutex<HashMap<String, FileStamp>>>,
+
+    // Search and Replace vars
+    replace_opts: SearchOptions,
+    replace_edits: Vec<ReplaceEdit>,
+    review_index: usize,
+    review_state: ListState,
+    stale_files: Vec<String>,
+    last_replacement: String,
 
     // State Variables
     switch_focus: Focus,
@@ -132,12 +288,48 @@ pub struct Vuit {
     recent_state: ListState,
     help_menu_state: ListState,
     preview_toggle: bool,
-    first_term_open: bool,
+    // Kept in marking order, which is the order marked files open in
+    marked: IndexSet<String>,
+    marks_context: Context,
+    // Set once a file went to the editor, --oneshot exits then
+    editor_opened: bool,
 
     // Termination
     exit: bool,
 }
 
+// Start offsets of the accepted matches, by file and then by line
+fn accepted_matches(edits: &[ReplaceEdit]) -> HashMap<String, HashMap<usize, HashSet<usize>>> {
+    let mut accepted: HashMap<String, HashMap<usize, HashSet<usize>>> = HashMap::new();
+    for edit in edits.iter().filter(|edit| edit.accepted) {
+        accepted
+            .entry(edit.file.clone())
+            .or_default()
+            .entry(edit.line_number)
+            .or_default()
+            .insert(edit.start);
+    }
+    accepted
+}
+
+// Stamps of the files named in search result entries, as they are now
+fn capture_stamps(entries: &[String]) -> HashMap<String, FileStamp> {
+    let mut stamps = HashMap::new();
+    for entry in entries {
+        let (file, _) = split_search_result(entry);
+        if stamps.contains_key(file) {
+            continue;
+        }
+        if let Ok(content) = fs::read(file) {
+            stamps.insert(
+                file.to_string(),
+                FileStamp::capture(Path::new(file), &content),
+            );
+        }
+    }
+    stamps
+}
+
 // Implementing Vuit
 impl Vuit {
     fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
@@ -147,7 +339,6 @@ impl Vuit {
         // Initialize Context
         self.switch_context = Context::Fileviewer;
         self.preview_toggle = false;
-        self.first_term_open = true;
 
         // Populate fd list
         self.run_fd_cmd();
@@ -164,8 +355,14 @@ impl Vuit {
         self.preview = self.run_preview_cmd();
 
         // Start terminal Process
+        self.last_task = manifests::load_last();
         start_term(self);
 
+        // Open with the locations of an errorfile given on the command line
+        if let Some(path) = self.errorfile.take() {
+            self.load_errorfile(&path);
+        }
+
         // Start Vuit
         while !self.exit {
             terminal.draw(|frame| dispatch_render(self, frame))?;
@@ -190,13 +387,14 @@ impl Vuit {
         self.fd_list = WalkBuilder::new(".")
             .standard_filters(true)
             .hidden(false)
-            .filter_entry(|entry| Vuit::skip_git(entry))
+            .filter_entry(Vuit::skip_git)
             .build()
             .filter_map(Result::ok)
             .map(|entry| entry.path().to_path_buf())
             .filter(|path| path.is_file())
             .filter_map(|path| path.to_str().map(String::from))
             .collect();
+        self.term_candidates = completion_candidates(&self.fd_list);
     }
 
     fn run_search_cmd(&mut self) -> Vec<String> {
@@ -215,57 +413,427 @@ impl Vuit {
     }
 
     fn start_async_search(&mut self) {
-        let search = self.typed_input.to_lowercase();
-        let file_list = self.file_list.clone();
+        self.spawn_search(
+            self.typed_input.clone(),
+            self.search_opts,
+            self.file_list.clone(),
+        );
+    }
+
+    fn spawn_search(&mut self, query: String, opts: SearchOptions, file_list: Vec<String>) {
+        let matcher = match build_matcher(&query, &opts) {
+            Ok(matcher) => matcher,
+            Err(_) => {
+                self.search_progress_str = "Invalid regex".to_string();
+                return;
+            }
+        };
+
+        // Stop any search still running, each search gets its own shared state
+        self.cancel_search();
+        self.search_progress = Arc::new(AtomicUsize::new(0));
+        self.search_cancel = Arc::new(AtomicBool::new(false));
+        self.search_result = Arc::new(Mutex::new(Vec::new()));
+        self.search_stamps = Arc::new(Mutex::new(HashMap::new()));
+        self.results_query = Some((query, opts));
+
         let progress = Arc::clone(&self.search_progress);
+        let cancel = Arc::clone(&self.search_cancel);
         let result = Arc::clone(&self.search_result);
+        let stamps = Arc::clone(&self.search_stamps);
 
         self.search_in_progress = true;
+        self.search_total = file_list.len();
+        self.search_progress_str.clear();
+        self.status_msg.clear();
+        // Marks and the selection were made in the results this search replaces
+        self.file_str_list.clear();
+        self.marked.clear();
+        self.hltd_file = 0;
+        self.file_str_list_state.select(None);
 
-        progress.store(0, Ordering::Relaxed);
         thread::spawn(move || {
-            let matches: Vec<String> = file_list
-                .par_iter()
-                .flat_map_iter(|path_str| {
-                    let path = Path::new(path_str);
-                    let file = match File::open(path) {
-                        Ok(f) => f,
-                        Err(_) => {
-                            progress.fetch_add(1, Ordering::Relaxed);
-                            return Some(vec![]);
-                        }
-                    };
-                    let reader = BufReader::new(file);
-
-                    let mut file_matches = Vec::new();
-
-                    for (line_number, line) in reader.lines().enumerate() {
-                        if let Ok(line) = line {
-                            if memmem::find(line.to_lowercase().as_bytes(), search.as_bytes())
-                                .is_some()
-                            {
-                                file_matches.push(clean_utf8_content(&format!(
-                                    "{}:{}:{}",
-                                    path.display(),
-                                    line_number + 1,
-                                    line
-                                )));
-                            }
-                        }
+            file_list.par_iter().for_each(|path_str| {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                let path = Path::new(path_str);
+                let content = match fs::read(path) {
+                    Ok(content) => content,
+                    Err(_) => {
+                        progress.fetch_add(1, Ordering::Release);
+                        return;
                     }
+                };
 
-                    progress.fetch_add(1, Ordering::Relaxed);
-                    Some(file_matches)
-                })
-                .flatten()
-                .collect();
+                let mut searcher = SearcherBuilder::new()
+                    .binary_detection(BinaryDetection::quit(b'\x00'))
+                    .line_number(true)
+                    .build();
 
-            if let Ok(mut lock) = result.lock() {
-                *lock = Some(matches);
-            }
+                let mut file_matches = Vec::new();
+
+                let _ = searcher.search_slice(
+                    &matcher,
+                    &content,
+                    Lossy(|line_number, line| {
+                        file_matches.push(clean_utf8_content(&format!(
+                            "{}:{}:{}",
+                            path.display(),
+                            line_number,
+                            line.trim_end_matches(['\r', '\n'])
+                        )));
+                        Ok(!cancel.load(Ordering::Relaxed))
+                    }),
+                );
+
+                // Remember what the file looked like so replace can detect later edits
+                if !file_matches.is_empty()
+                    && let Ok(mut lock) = stamps.lock()
+                {
+                    lock.insert(
+                        clean_utf8_content(&path.display().to_string()),
+                        FileStamp::capture(path, &content),
+                    );
+                }
+
+                // Results are published before the file is counted as done
+                if !file_matches.is_empty()
+                    && let Ok(mut lock) = result.lock()
+                {
+                    lock.append(&mut file_matches);
+                }
+                progress.fetch_add(1, Ordering::Release);
+            });
         });
     }
 
+    fn collect_search_results(&mut self) {
+        if !self.search_in_progress {
+            return;
+        }
+
+        let done = self.search_progress.load(Ordering::Acquire) == self.search_total;
+
+        if let Ok(mut result) = self.search_result.lock() {
+            self.file_str_list.append(&mut result);
+        }
+
+        if done {
+            self.search_in_progress = false;
+        }
+    }
+
+    // Replaces the string search results with the locations of an errorfile or compiler log
+    fn load_errorfile(&mut self, path: &str) {
+        let path = expand_tilde(path);
+
+        self.cancel_search();
+        self.prev_context = self.switch_context;
+        self.switch_context = Context::Stringsearch;
+
+        match quickfix::read_errorfile(&path) {
+            Ok(entries) if entries.is_empty() => {
+                self.status_msg = format!("No locations found in {}", path.display());
+            }
+            Ok(entries) => {
+                self.status_msg =
+                    format!("Loaded {} locations from {}", entries.len(), path.display());
+                self.search_progress_str = format!("{} Loaded", entries.len());
+                self.marked.clear();
+                self.results_query = None;
+                self.switch_focus = Focus::Filestrlist;
+                self.hltd_file = 0;
+                self.file_list_state.select(None);
+                self.recent_state.select(None);
+                // Replacing in these files checks them against how they were when loaded
+                self.search_stamps = Arc::new(Mutex::new(capture_stamps(&entries)));
+                self.file_str_list = entries;
+                self.file_str_list_state.select(Some(0));
+                self.preview = self.run_preview_cmd();
+            }
+            Err(e) => {
+                self.status_msg = format!("Failed to read {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn cancel_search(&mut self) {
+        if !self.search_in_progress {
+            return;
+        }
+
+        self.search_cancel.store(true, Ordering::Relaxed);
+        self.collect_search_results();
+        self.search_in_progress = false;
+        self.search_progress_str = format!(
+            "{} Matches | Cancelled at {} / {}",
+            self.file_str_list.len(),
+            self.search_progress.load(Ordering::Relaxed),
+            self.search_total
+        );
+    }
+
+    // Opens a file in the configured editor, at a position when one is given
+    fn open_in_editor(
+        &mut self,
+        file_path: &str,
+        position: Option<Position>,
+        terminal: &mut DefaultTerminal,
+    ) {
+        if self.sent_to_nvim(|address| nvim::open(address, file_path, position)) {
+            return;
+        }
+
+        let template = editor::template(&self.config.editor, &self.config.editor_template);
+        let args = editor::command(template, &self.config.editor, file_path, position);
+        self.launch_editor(&args, terminal);
+    }
+
+    // Opens every marked file in one editor, laid out as configured
+    fn open_marked(&mut self, terminal: &mut DefaultTerminal) {
+        let files: Vec<String> = std::mem::take(&mut self.marked)
+            .iter()
+            .map(|entry| split_search_result(entry).0.to_string())
+            .unique()
+            .collect();
+
+        for file in files.iter() {
+            if !self.recent_files.contains(file) {
+                self.recent_files.push(file.clone());
+            }
+        }
+        while self.recent_files.len() > 5 {
+            self.recent_files.remove(0);
+        }
+
+        let layout = self.config.open_layout;
+        if self.sent_to_nvim(|address| nvim::open_many(address, &files, layout)) {
+            return;
+        }
+
+        let template = editor::template(&self.config.editor, &self.config.editor_template);
+        let args = editor::command_many(template, &self.config.editor, &files, layout);
+        self.launch_editor(&args, terminal);
+    }
+
+    // Hands the marked search results, else all of them, to the editor as a quickfix list
+    fn send_to_quickfix(&mut self, terminal: &mut DefaultTerminal) {
+        let entries: Vec<String> = if self.marked.is_empty() {
+            self.file_str_list.clone()
+        } else {
+            std::mem::take(&mut self.marked).into_iter().collect()
+        };
+        let entries: Vec<String> = entries
+            .into_iter()
+            .filter(|entry| split_search_result(entry).1.is_some())
+            .collect();
+        if entries.is_empty() {
+            self.status_msg = "No search results to send".to_string();
+            return;
+        }
+
+        let errorfile = match quickfix::write_errorfile(&entries) {
+            Ok(file) => {
+                let path = file.path().display().to_string();
+                // Kept until the next list or until vuit exits, the editor reads it meanwhile
+                self.quickfix_file = Some(file);
+                path
+            }
+            Err(e) => {
+                self.status_msg = format!("Failed to write the quickfix list: {}", e);
+                return;
+            }
+        };
+
+        if self.sent_to_nvim(|address| nvim::open_quickfix(address, &errorfile)) {
+            return;
+        }
+
+        let files: Vec<String> = entries
+            .iter()
+            .map(|entry| split_search_result(entry).0.to_string())
+            .unique()
+            .collect();
+        let template = editor::template(&self.config.editor, &self.config.editor_template);
+        let args = editor::quickfix_command(template, &self.config.editor, &errorfile, &files);
+        self.launch_editor(&args, terminal);
+    }
+
+    // A running Neovim takes the files as buffers and vuit keeps going
+    fn sent_to_nvim(&mut self, send: impl FnOnce(&str) -> io::Result<()>) -> bool {
+        let Some(address) = nvim::address(&self.config.nvim_socket) else {
+            return false;
+        };
+
+        match send(&address) {
+            Ok(()) => {
+                self.editor_opened = true;
+                true
+            }
+            Err(e) => {
+                self.status_msg = format!("Neovim at {} failed to open the file: {}", address, e);
+                false
+            }
+        }
+    }
+
+    fn launch_editor(&mut self, args: &[String], terminal: &mut DefaultTerminal) {
+        if args.is_empty() {
+            self.status_msg = "Editor template is empty".to_string();
+            return;
+        }
+
+        let mode = editor::launch_mode(&self.config.editor, self.config.editor_mode);
+        let result = if mode == LaunchMode::Detached {
+            editor::run_detached(args)
+        } else if self.open_pane(args) {
+            Ok(())
+        } else {
+            // The editor got the terminal without mouse capture
+            self.mouse_captured = false;
+            editor::run_inline(terminal, args)
+        };
+
+        match result {
+            Ok(()) => self.editor_opened = true,
+            Err(e) => self.status_msg = format!("Failed to start {}: {}", args[0], e),
+        }
+    }
+
+    // Marks belong to the list of the context they were made in, leaving it drops them
+    fn sync_marks(&mut self) {
+        if self.switch_context != self.marks_context {
+            self.marked.clear();
+            self.marks_context = self.switch_context;
+        }
+    }
+
+    // Mouse capture only while the Terminal is shown, so text can be selected everywhere else
+    fn sync_mouse_capture(&mut self) {
+        let wanted = self.switch_context == Context::Terminal;
+        if wanted == self.mouse_captured {
+            return;
+        }
+
+        let result = if wanted {
+            execute!(io::stdout(), EnableMouseCapture)
+        } else {
+            execute!(io::stdout(), DisableMouseCapture)
+        };
+        if result.is_ok() {
+            self.mouse_captured = wanted;
+        }
+    }
+
+    // Runs a program, or a shell without args, in a pane next to vuit. False outside a
+    // multiplexer or when the pane could not be opened
+    fn open_pane(&mut self, args: &[String]) -> bool {
+        let Some(multiplexer) = Multiplexer::from_config(&self.config) else {
+            return false;
+        };
+
+        match multiplexer.open(args) {
+            Ok(()) => true,
+            Err(e) => {
+                // Shown in the Terminal every caller falls back to
+                self.term_msg = format!(" Failed to open a {} pane: {} ", multiplexer.name(), e);
+                false
+            }
+        }
+    }
+
+    // A shell in a multiplexer pane, else the embedded Terminal
+    fn open_shell(&mut self) {
+        if self.open_pane(&[]) {
+            return;
+        }
+        self.typed_input.clear();
+        self.prev_context = self.switch_context;
+        self.switch_context = Context::Terminal;
+    }
+
+    // Runs a file in a multiplexer pane, else in the embedded Terminal's shell
+    fn run_file(&mut self, abs_path: String) {
+        let script = [
+            self.config.shell.clone(),
+            "-c".to_string(),
+            abs_path.clone(),
+        ];
+        if self.open_pane(&script) {
+            return;
+        }
+        self.typed_input.clear();
+        self.prev_context = self.switch_context;
+        self.switch_context = Context::Terminal;
+        if !self.term_sessions.is_empty() {
+            self.typed_input = abs_path;
+            send_cmd_to_proc_term(self);
+        }
+        self.typed_input.clear();
+    }
+
+    fn focused_list(&self) -> &Vec<String> {
+        match self.switch_focus {
+            Focus::Recentfiles => &self.recent_files,
+            Focus::Filelist => &self.file_list,
+            Focus::Filestrlist => &self.file_str_list,
+        }
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(entry) = self.focused_list().get(self.hltd_file).cloned() else {
+            return;
+        };
+
+        if !self.marked.shift_remove(&entry) {
+            self.marked.insert(entry);
+        }
+    }
+
+    // Marks every entry of the list in focus
+    fn mark_all(&mut self) {
+        let entries = self.focused_list().clone();
+        self.marked.extend(entries);
+    }
+
+    // Flips the marks of the list in focus, marks in other lists are left alone
+    fn invert_marks(&mut self) {
+        let entries: IndexSet<String> = self.focused_list().iter().cloned().collect();
+        let unmarked: Vec<String> = entries
+            .iter()
+            .filter(|entry| !self.marked.contains(*entry))
+            .cloned()
+            .collect();
+        self.marked.retain(|marked| !entries.contains(marked));
+        self.marked.extend(unmarked);
+    }
+
+    // Check box in front of list entries, only shown while something is marked
+    fn mark_flag(&self, entry: &str) -> &'static str {
+        if self.marked.is_empty() {
+            ""
+        } else if self.marked.contains(entry) {
+            "[x] "
+        } else {
+            "[ ] "
+        }
+    }
+
+    fn marked_title(&self, title: &str) -> String {
+        if self.marked.is_empty() {
+            format!(" {} ", title)
+        } else {
+            format!(
+                " {} [ {} marked | {} ] ",
+                title,
+                self.marked.len(),
+                self.config.open_layout.label()
+            )
+        }
+    }
+
     fn set_clipboard(text: &str) -> Result<(), Box<dyn Error>> {
         let mut clipboard = arboard::Clipboard::new()?;
         clipboard.set_text(text.to_owned())?;
@@ -273,27 +841,50 @@ impl Vuit {
         Ok(())
     }
 
-    fn replace_string_occurences(&mut self) {
+    // Replaces what the search found, an errorfile's locations take the typed query
+    fn start_replace(&mut self) {
+        let (query, opts) = self
+            .results_query
+            .clone()
+            .unwrap_or_else(|| (self.typed_input.clone(), self.search_opts));
+        self.current_str_filter = query;
+        self.replace_opts = opts;
+        self.typed_input.clear();
+        self.prev_context = self.switch_context;
+        self.switch_context = Context::Stringsearchreplace;
+    }
+
+    fn build_replace_edits(&mut self) {
+        self.replace_edits.clear();
+        self.review_index = 0;
+
         if self.current_str_filter.is_empty() {
             return;
         }
 
-        let mut file_cache: HashMap<String, Vec<String>> = HashMap::new();
+        let matcher = match build_matcher(&self.current_str_filter, &self.replace_opts) {
+            Ok(matcher) => matcher,
+            Err(_) => return,
+        };
+
+        let mut file_cache: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+        let mut seen = HashSet::new();
 
         for entry in self.file_str_list.iter() {
-            let parts: Vec<&str> = entry.split(':').collect();
-            if parts.len() < 3 {
+            let (file_path, Some(line_number)) = split_search_result(entry) else {
                 continue;
-            }
-            let file_path = parts[0].to_string();
-            let line_number: usize = match parts[1].parse() {
-                Ok(num) => num,
-                Err(_) => continue,
             };
+            if !seen.insert((file_path, line_number)) {
+                continue;
+            }
 
-            let lines = file_cache.entry(file_path.clone()).or_insert_with(|| {
-                read_to_string(&file_path)
-                    .map(|content| content.lines().map(|line| line.to_string()).collect())
+            let lines = file_cache.entry(file_path.to_string()).or_insert_with(|| {
+                fs::read(file_path)
+                    .map(|content| {
+                        split_lines(&content)
+                            .map(|line| strip_terminator(line).0.to_vec())
+                            .collect()
+                    })
                     .unwrap_or_default()
             });
 
@@ -301,83 +892,240 @@ impl Vuit {
                 continue;
             }
 
-            lines[line_number - 1] =
-                lines[line_number - 1].replace(&self.current_str_filter, &self.typed_input);
+            let before = &lines[line_number - 1];
+            for range in match_ranges(&matcher, before) {
+                let after = replace_matches(
+                    &matcher,
+                    before,
+                    &self.typed_input,
+                    &self.replace_opts,
+                    |found| found == range,
+                );
+                if after == *before {
+                    continue;
+                }
+
+                self.replace_edits.push(ReplaceEdit {
+                    file: file_path.to_string(),
+                    line_number,
+                    start: range.start,
+                    before: String::from_utf8_lossy(before).into_owned(),
+                    after: String::from_utf8_lossy(&after).into_owned(),
+                    accepted: true,
+                });
+            }
+        }
+    }
+
+    fn replace_string_occurences(&mut self) {
+        let matcher = match build_matcher(&self.current_str_filter, &self.replace_opts) {
+            Ok(matcher) => matcher,
+            Err(_) => return,
+        };
+
+        let accepted = accepted_matches(&self.replace_edits);
+
+        let stamps = self
+            .search_stamps
+            .lock()
+            .map(|stamps| stamps.clone())
+            .unwrap_or_default();
+
+        let mut failed = Vec::new();
+        let mut writes = Vec::new();
+        self.stale_files.clear();
+
+        for (filename, matches) in accepted {
+            match fs::read(&filename) {
+                // Files edited since the search would get the wrong lines rewritten
+                Ok(original)
+                    if stamps.get(&filename)
+                        != Some(&FileStamp::capture(Path::new(&filename), &original)) =>
+                {
+                    self.stale_files.push(filename);
+                }
+                Ok(original) => {
+                    let content = rewrite_lines(
+                        &original,
+                        &matches,
+                        &matcher,
+                        &self.typed_input,
+                        &self.replace_opts,
+                    );
+                    let num_matches = matches.values().map(HashSet::len).sum::<usize>();
+                    writes.push((filename, original, content, num_matches));
+                }
+                Err(_) => failed.push(filename),
+            }
+        }
+
+        // Keep the original bytes so the replace can be undone
+        let journal: Vec<JournalWrite> = writes
+            .iter()
+            .map(|(filename, original, content, _)| JournalWrite {
+                path: Path::new(filename),
+                original,
+                written: content,
+            })
+            .collect();
+
+        if !journal.is_empty()
+            && let Err(e) = journal::record(&journal)
+        {
+            self.status_msg = format!("Replace aborted, could not write undo journal: {}", e);
+            return;
+        }
+
+        let journaled = journal.len();
+        let mut replaced_matches = 0;
+        let mut written = Vec::new();
+        for (write, (filename, _, content, num_matches)) in journal.into_iter().zip(writes.iter()) {
+            match write_atomic(Path::new(filename), content) {
+                Ok(()) => {
+                    replaced_matches += num_matches;
+                    written.push(write);
+                }
+                Err(_) => failed.push(filename.clone()),
+            }
         }
 
-        for (filename, lines) in file_cache {
-            let content = lines.join("\n");
-            let _ = write(&filename, content);
+        self.status_msg = format!(
+            "Replaced {} matches in {} files",
+            replaced_matches,
+            written.len()
+        );
+        if !failed.is_empty() {
+            self.status_msg += &format!(", failed to write: {}", failed.join(", "));
+        }
+        // Files that were never written have nothing to undo
+        if written.len() < journaled
+            && let Err(e) = journal::record(&written)
+        {
+            self.status_msg += &format!(", could not update the undo journal: {}", e);
         }
+        if !self.stale_files.is_empty() {
+            self.stale_files.sort();
+            self.status_msg += &format!(
+                ", skipped files changed since the search: {} (<C-g> to re-search and retry)",
+                self.stale_files.join(", ")
+            );
+        }
+        self.last_replacement = self.typed_input.clone();
+        self.replace_edits.clear();
         self.file_str_list.clear();
         self.typed_input.clear();
     }
 
-    fn run_preview_cmd(&mut self) -> Vec<String> {
-        if !self.preview_toggle {
-            return vec![];
+    fn retry_stale_replace(&mut self) {
+        if self.stale_files.is_empty() {
+            self.status_msg = "No skipped files to retry".to_string();
+            return;
         }
 
-        let file_list = match self.switch_focus {
-            Focus::Recentfiles => &self.recent_files,
-            Focus::Filelist => &self.file_list,
-            Focus::Filestrlist => &self.file_str_list,
+        // Search the skipped files again so the replace can be reviewed against their new contents
+        let stale_files = std::mem::take(&mut self.stale_files);
+        self.spawn_search(
+            self.current_str_filter.clone(),
+            self.replace_opts,
+            stale_files,
+        );
+        self.typed_input = self.last_replacement.clone();
+    }
+
+    fn undo_last_replace(&mut self) {
+        self.status_msg = match journal::undo_last() {
+            Ok(report) => {
+                let mut msg = format!("Undo restored {} files", report.restored.len());
+                if !report.modified.is_empty() {
+                    msg += &format!(
+                        ", refused files edited since the replace: {}",
+                        report.modified.join(", ")
+                    );
+                }
+                if !report.failed.is_empty() {
+                    msg += &format!(", failed to restore: {}", report.failed.join(", "));
+                }
+                msg
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => "Nothing to undo".to_string(),
+            Err(e) => format!("Undo failed: {}", e),
         };
+    }
 
-        if file_list.is_empty() {
+    fn run_preview_cmd(&mut self) -> Vec<String> {
+        if !self.preview_toggle {
             return vec![];
         }
 
-        let file_path = if self.switch_context == Context::Stringsearch
-            || self.switch_context == Context::Stringsearchreplace
-        {
-            let parts: Vec<&str> = file_list[self.hltd_file].split(':').collect();
-            parts[0].to_string()
+        let (file_path, line_number) = if self.switch_context == Context::Replacereview {
+            match self.replace_edits.get(self.review_index) {
+                Some(edit) => (edit.file.clone(), edit.line_number),
+                None => return vec![],
+            }
         } else {
+            let file_list = match self.switch_focus {
+                Focus::Recentfiles => &self.recent_files,
+                Focus::Filelist => &self.file_list,
+                Focus::Filestrlist => &self.file_str_list,
+            };
+
             if self.hltd_file >= file_list.len() {
                 return vec![];
             }
-            file_list[self.hltd_file].to_string()
-        };
 
-        let num_lines =
-            if self.switch_context == Context::Terminal || self.switch_context == Context::Help {
-                PREVIEW_NUM_LINES - TERMINAL_NUM_LINES
+            if self.switch_context == Context::Stringsearch
+                || self.switch_context == Context::Stringsearchreplace
+            {
+                let mut parts = file_list[self.hltd_file].split(':');
+                let file_path = parts.next().unwrap_or_default().to_string();
+                let line_number: usize = if self.switch_focus == Focus::Filestrlist {
+                    parts
+                        .next()
+                        .and_then(|num| num.parse().ok())
+                        .unwrap_or_default()
+                } else {
+                    0
+                };
+                (file_path, line_number)
             } else {
-                PREVIEW_NUM_LINES
-            };
+                (file_list[self.hltd_file].to_string(), 0)
+            }
+        };
+
+        let num_lines = if self.switch_context == Context::Terminal
+            || self.switch_context == Context::Tasks
+            || self.switch_context == Context::Help
+        {
+            PREVIEW_NUM_LINES - TERMINAL_NUM_LINES
+        } else {
+            PREVIEW_NUM_LINES
+        };
 
         let num_lines: usize = num_lines as usize;
 
-        match File::open(file_path) {
-            Ok(file) => {
-                let reader = BufReader::new(file);
-                if self.switch_context == Context::Stringsearch {
-                    let parts: Vec<&str> = file_list[self.hltd_file].split(':').collect();
-                    let line_number: usize = match parts[1].parse() {
-                        Ok(num) => num,
-                        Err(_) => 0,
-                    };
-                    let lines = reader
-                        .lines()
-                        .skip(line_number - 1)
-                        .take(num_lines)
-                        .filter_map(Result::ok)
-                        .map(|line| clean_utf8_content(&line))
-                        .collect::<Vec<String>>();
-                    return lines;
-                } else {
-                    let lines = reader
-                        .lines()
-                        .take(num_lines)
-                        .filter_map(Result::ok)
-                        .map(|line| clean_utf8_content(&line))
-                        .collect::<Vec<String>>();
-                    return lines;
-                }
-            }
-            Err(_) => vec!["No Preview Available".to_string()],
+        let mut lines = match File::open(file_path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .skip(line_number.saturating_sub(1))
+                .take(num_lines)
+                .filter_map(Result::ok)
+                .collect::<Vec<String>>(),
+            Err(_) => return vec!["No Preview Available".to_string()],
+        };
+
+        // Show the typed replacement live against the highlighted match
+        if self.switch_context == Context::Stringsearchreplace
+            && line_number > 0
+            && !lines.is_empty()
+            && let Ok(matcher) = build_matcher(&self.current_str_filter, &self.replace_opts)
+        {
+            let before = lines.remove(0);
+            let after = replace_line(&matcher, &before, &self.typed_input, &self.replace_opts);
+            lines.insert(0, format!("- {}", before));
+            lines.insert(1, format!("+ {}", after));
         }
+
+        lines.iter().map(|line| clean_utf8_content(line)).collect()
     }
 }
 
@@ -392,6 +1140,12 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                 .help("Run once and exit after selection")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("errorfile")
+                .long("errorfile")
+                .value_name("PATH")
+                .help("Load the locations of a compiler log or quickfix file as search results"),
+        )
         .get_matches();
 
     if matches.contains_id("version") {
@@ -427,8 +1181,10 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     if matches.get_flag("oneshot") {
         vuit_app.oneshot = true;
     }
+    vuit_app.errorfile = matches.get_one::<String>("errorfile").cloned();
 
     let vuit_result = vuit_app.run(&mut terminal);
+    let _ = execute!(io::stdout(), DisableMouseCapture);
     ratatui::restore();
 
     if let Err(e) = vuit_result {
@@ -437,3 +1193,189 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vuit::search::CaseMode;
+
+    #[test]
+    fn loaded_locations_stamp_each_file_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "fn main() {}\n").unwrap();
+        let file = path.display().to_string();
+        let missing = dir.path().join("gone.rs").display().to_string();
+
+        let entries = [
+            format!("{}:1:first", file),
+            format!("{}:1:second", file),
+            format!("{}:3:missing", missing),
+        ];
+        let stamps = capture_stamps(&entries);
+        assert_eq!(stamps.len(), 1);
+        assert!(stamps[&file] == FileStamp::capture(&path, b"fn main() {}\n"));
+    }
+
+    #[test]
+    fn unreadable_errorfile_leaves_the_focus_and_results_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("typo.log");
+        let mut app = Vuit::default();
+
+        app.load_errorfile(&missing.display().to_string());
+        assert!(app.status_msg.starts_with("Failed to read"));
+        assert!(app.switch_focus != Focus::Filestrlist);
+        assert!(app.file_str_list.is_empty());
+        assert_eq!(app.file_str_list_state.selected(), None);
+
+        let empty = dir.path().join("empty.log");
+        fs::write(&empty, "nothing to see\n").unwrap();
+        app.load_errorfile(&empty.display().to_string());
+        assert!(app.status_msg.starts_with("No locations found"));
+        assert!(app.switch_focus != Focus::Filestrlist);
+    }
+
+    #[test]
+    fn marks_keep_their_order_through_toggles_and_inversions() {
+        let mut app = Vuit {
+            file_list: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            ..Default::default()
+        };
+
+        app.hltd_file = 2;
+        app.toggle_mark();
+        app.mark_all();
+        assert_eq!(app.marked.iter().collect::<Vec<_>>(), ["c", "a", "b"]);
+
+        app.hltd_file = 0;
+        app.toggle_mark();
+        app.invert_marks();
+        assert_eq!(app.marked.iter().collect::<Vec<_>>(), ["a"]);
+        assert_eq!(app.mark_flag("a"), "[x] ");
+        assert_eq!(app.mark_flag("b"), "[ ] ");
+    }
+
+    #[test]
+    fn a_new_search_starts_without_the_old_selection() {
+        let mut app = Vuit {
+            file_str_list: vec!["a.rs:1:x".to_string(), "b.rs:2:x".to_string()],
+            hltd_file: 1,
+            ..Default::default()
+        };
+        app.file_str_list_state.select(Some(1));
+
+        app.spawn_search("x".to_string(), app.search_opts, Vec::new());
+        assert_eq!(app.hltd_file, 0);
+        assert_eq!(app.file_str_list_state.selected(), None);
+        assert!(app.file_str_list.is_empty());
+    }
+
+    #[test]
+    fn replace_uses_the_query_and_modes_of_the_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "foo();\n").unwrap();
+        let file = path.display().to_string();
+
+        let mut app = Vuit {
+            typed_input: "Foo".to_string(),
+            ..Default::default()
+        };
+        app.spawn_search("Foo".to_string(), app.search_opts, Vec::new());
+        app.file_str_list = vec![format!("{}:1:foo();", file)];
+
+        // Modes switched after the search do not change what gets replaced
+        app.search_opts.case = CaseMode::Sensitive;
+        app.typed_input = "Other".to_string();
+        app.start_replace();
+        assert_eq!(app.current_str_filter, "Foo");
+
+        app.typed_input = "bar".to_string();
+        app.build_replace_edits();
+        assert_eq!(app.replace_edits.len(), 1);
+        assert_eq!(app.replace_edits[0].after, "bar();");
+    }
+
+    #[test]
+    fn files_changed_since_the_search_are_skipped_and_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "old();\n").unwrap();
+        let file = path.display().to_string();
+
+        let entries = vec![format!("{}:1:old();", file)];
+        let mut app = Vuit {
+            current_str_filter: "old".to_string(),
+            typed_input: "new".to_string(),
+            search_stamps: Arc::new(Mutex::new(capture_stamps(&entries))),
+            file_str_list: entries,
+            ..Default::default()
+        };
+        app.build_replace_edits();
+        assert_eq!(app.replace_edits.len(), 1);
+
+        // Edited after the search, the replace must not touch it
+        fs::write(&path, "old(); // edited\n").unwrap();
+        app.replace_string_occurences();
+        assert_eq!(fs::read(&path).unwrap(), b"old(); // edited\n");
+        assert_eq!(app.stale_files, std::slice::from_ref(&file));
+        assert!(
+            app.status_msg
+                .contains("skipped files changed since the search")
+        );
+
+        // Retrying searches the skipped file again with the same replacement
+        app.retry_stale_replace();
+        assert!(app.stale_files.is_empty());
+        assert_eq!(app.typed_input, "new");
+        while app.search_in_progress {
+            thread::sleep(Duration::from_millis(10));
+            app.collect_search_results();
+        }
+        assert_eq!(app.file_str_list, [format!("{}:1:old(); // edited", file)]);
+    }
+
+    #[test]
+    fn each_match_of_a_line_is_accepted_or_rejected_on_its_own() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "let old = old + 1;\nold();\n").unwrap();
+        let file = path.display().to_string();
+
+        let mut app = Vuit {
+            current_str_filter: "old".to_string(),
+            typed_input: "new".to_string(),
+            file_str_list: vec![
+                format!("{}:1:let old = old + 1;", file),
+                format!("{}:2:old();", file),
+            ],
+            ..Default::default()
+        };
+        app.build_replace_edits();
+
+        let afters: Vec<&str> = app
+            .replace_edits
+            .iter()
+            .map(|edit| edit.after.as_str())
+            .collect();
+        assert_eq!(
+            afters,
+            ["let new = old + 1;", "let old = new + 1;", "new();"]
+        );
+
+        // Reject the first match on line 1 and the call on line 2
+        app.replace_edits[0].accepted = false;
+        app.replace_edits[2].accepted = false;
+        let accepted = accepted_matches(&app.replace_edits);
+        let matcher = build_matcher("old", &app.replace_opts).unwrap();
+        let content = rewrite_lines(
+            &fs::read(&path).unwrap(),
+            &accepted[&file],
+            &matcher,
+            "new",
+            &app.replace_opts,
+        );
+        assert_eq!(content, b"let old = new + 1;\nold();\n");
+    }
+}
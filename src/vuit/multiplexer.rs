@@ -0,0 +1,264 @@
+use crate::vuit::VuitRC;
+use serde::{Deserialize, Serialize};
+use std::{env, io, path::PathBuf, process::Command};
+
+// Terminal multiplexers and emulators vuit can open panes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Tmux,
+    Zellij,
+    Screen,
+    Kitty,
+    Wezterm,
+    // Never use a multiplexer, even when running in one
+    None,
+}
+
+// Where a new pane goes relative to vuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    // Side by side with vuit
+    #[default]
+    Right,
+    // Under vuit
+    Below,
+    // A new window or tab
+    Window,
+}
+
+pub struct Multiplexer {
+    backend: Backend,
+    placement: Placement,
+    // Percent of the split the new pane takes
+    size: u16,
+}
+
+impl Multiplexer {
+    // Configured backend, else the multiplexer vuit is running in
+    pub fn from_config(config: &VuitRC) -> Option<Self> {
+        let backend = match config.multiplexer {
+            Some(Backend::None) => return None,
+            Some(backend) => backend,
+            None => detect()?,
+        };
+
+        Some(Self {
+            backend,
+            placement: config.pane_placement,
+            size: config.pane_size.clamp(10, 90),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.backend {
+            Backend::Tmux => "tmux",
+            Backend::Zellij => "zellij",
+            Backend::Screen => "screen",
+            Backend::Kitty => "kitty",
+            Backend::Wezterm => "wezterm",
+            Backend::None => "none",
+        }
+    }
+
+    // Runs a program in a new pane, or a shell when there are no args
+    pub fn open(&self, args: &[String]) -> io::Result<()> {
+        let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        run_all(&self.commands(args, &cwd.display().to_string()))
+    }
+
+    // Backend commands that open the pane in cwd, run one after the other
+    fn commands(&self, args: &[String], cwd: &str) -> Vec<Vec<String>> {
+        let size = self.size.to_string();
+
+        match self.backend {
+            Backend::Tmux => {
+                let mut cmd = words(&["tmux"]);
+                match self.placement {
+                    Placement::Right => cmd.extend(words(&["split-window", "-h", "-l"])),
+                    Placement::Below => cmd.extend(words(&["split-window", "-v", "-l"])),
+                    Placement::Window => cmd.push("new-window".to_string()),
+                }
+                if self.placement != Placement::Window {
+                    cmd.push(format!("{}%", size));
+                }
+                cmd.extend(words(&["-c", cwd]));
+                cmd.extend_from_slice(args);
+                vec![cmd]
+            }
+            // Tiled panes split evenly, zellij has no size for them
+            Backend::Zellij => {
+                let new_tab = words(&["zellij", "action", "new-tab", "--cwd", cwd]);
+                let mut cmd = if args.is_empty() {
+                    words(&["zellij", "action", "new-pane", "--cwd", cwd])
+                } else {
+                    words(&["zellij", "run", "--cwd", cwd])
+                };
+                match self.placement {
+                    Placement::Right => cmd.extend(words(&["--direction", "right"])),
+                    Placement::Below => cmd.extend(words(&["--direction", "down"])),
+                    // The program takes the place of the new tab's shell
+                    Placement::Window => cmd.push("--in-place".to_string()),
+                }
+                if !args.is_empty() {
+                    cmd.push("--".to_string());
+                    cmd.extend_from_slice(args);
+                }
+
+                match self.placement {
+                    Placement::Window if args.is_empty() => vec![new_tab],
+                    Placement::Window => vec![new_tab, cmd],
+                    _ => vec![cmd],
+                }
+            }
+            Backend::Screen => {
+                let mut cmd = words(&["screen", "-X", "eval", &format!("chdir {}", quote(cwd))]);
+                match self.placement {
+                    Placement::Right => cmd.extend(words(&["split -v", "focus"])),
+                    Placement::Below => cmd.extend(words(&["split", "focus"])),
+                    Placement::Window => {}
+                }
+                let program: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+                cmd.push(
+                    format!("screen {}", program.join(" "))
+                        .trim_end()
+                        .to_string(),
+                );
+                if self.placement != Placement::Window {
+                    cmd.push(format!("resize {}%", size));
+                }
+                vec![cmd]
+            }
+            Backend::Kitty => {
+                let mut cmd = words(&["kitty", "@", "launch", "--cwd", cwd]);
+                match self.placement {
+                    Placement::Right => cmd.extend(words(&["--location=vsplit", "--bias", &size])),
+                    Placement::Below => cmd.extend(words(&["--location=hsplit", "--bias", &size])),
+                    Placement::Window => cmd.push("--type=tab".to_string()),
+                }
+                cmd.extend_from_slice(args);
+                vec![cmd]
+            }
+            Backend::Wezterm => {
+                let mut cmd = match self.placement {
+                    Placement::Right => words(&["wezterm", "cli", "split-pane", "--right"]),
+                    Placement::Below => words(&["wezterm", "cli", "split-pane", "--bottom"]),
+                    Placement::Window => words(&["wezterm", "cli", "spawn"]),
+                };
+                if self.placement != Placement::Window {
+                    cmd.extend(words(&["--percent", &size]));
+                }
+                cmd.extend(words(&["--cwd", cwd]));
+                if !args.is_empty() {
+                    cmd.push("--".to_string());
+                    cmd.extend_from_slice(args);
+                }
+                vec![cmd]
+            }
+            Backend::None => Vec::new(),
+        }
+    }
+}
+
+// The multiplexer vuit runs in, innermost first, then the terminal emulator it runs in
+fn detect() -> Option<Backend> {
+    let set = |var: &str| env::var_os(var).is_some_and(|value| !value.is_empty());
+
+    if set("TMUX") {
+        Some(Backend::Tmux)
+    } else if set("ZELLIJ") {
+        Some(Backend::Zellij)
+    } else if set("STY") {
+        Some(Backend::Screen)
+    } else if set("KITTY_WINDOW_ID") || set("KITTY_LISTEN_ON") {
+        Some(Backend::Kitty)
+    } else if set("WEZTERM_PANE") {
+        Some(Backend::Wezterm)
+    } else {
+        None
+    }
+}
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+// Double quotes a word for screen's own command parser
+fn quote(word: &str) -> String {
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Runs the commands in order, stopping at the first that fails
+fn run_all(commands: &[Vec<String>]) -> io::Result<()> {
+    for command in commands {
+        let Some((program, args)) = command.split_first() else {
+            continue;
+        };
+        let output = Command::new(program).args(args).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(stderr.trim().to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_backend_and_placement_builds_its_commands() {
+        use Backend::*;
+        use Placement::*;
+        let args = ["vim".to_string(), r#"a "b".txt"#.to_string()];
+        let file = r#"a "b".txt"#;
+        let screen = r#"screen "vim" "a \"b\".txt""#;
+
+        #[rustfmt::skip]
+        let table: [(Backend, Placement, Vec<Vec<&str>>); 15] = [
+            (Tmux, Right, vec![vec!["tmux", "split-window", "-h", "-l", "30%", "-c", "/proj", "vim", file]]),
+            (Tmux, Below, vec![vec!["tmux", "split-window", "-v", "-l", "30%", "-c", "/proj", "vim", file]]),
+            (Tmux, Window, vec![vec!["tmux", "new-window", "-c", "/proj", "vim", file]]),
+            (Zellij, Right, vec![vec!["zellij", "run", "--cwd", "/proj", "--direction", "right", "--", "vim", file]]),
+            (Zellij, Below, vec![vec!["zellij", "run", "--cwd", "/proj", "--direction", "down", "--", "vim", file]]),
+            (Zellij, Window, vec![
+                vec!["zellij", "action", "new-tab", "--cwd", "/proj"],
+                vec!["zellij", "run", "--cwd", "/proj", "--in-place", "--", "vim", file],
+            ]),
+            (Screen, Right, vec![vec!["screen", "-X", "eval", r#"chdir "/proj""#, "split -v", "focus", screen, "resize 30%"]]),
+            (Screen, Below, vec![vec!["screen", "-X", "eval", r#"chdir "/proj""#, "split", "focus", screen, "resize 30%"]]),
+            (Screen, Window, vec![vec!["screen", "-X", "eval", r#"chdir "/proj""#, screen]]),
+            (Kitty, Right, vec![vec!["kitty", "@", "launch", "--cwd", "/proj", "--location=vsplit", "--bias", "30", "vim", file]]),
+            (Kitty, Below, vec![vec!["kitty", "@", "launch", "--cwd", "/proj", "--location=hsplit", "--bias", "30", "vim", file]]),
+            (Kitty, Window, vec![vec!["kitty", "@", "launch", "--cwd", "/proj", "--type=tab", "vim", file]]),
+            (Wezterm, Right, vec![vec!["wezterm", "cli", "split-pane", "--right", "--percent", "30", "--cwd", "/proj", "--", "vim", file]]),
+            (Wezterm, Below, vec![vec!["wezterm", "cli", "split-pane", "--bottom", "--percent", "30", "--cwd", "/proj", "--", "vim", file]]),
+            (Wezterm, Window, vec![vec!["wezterm", "cli", "spawn", "--cwd", "/proj", "--", "vim", file]]),
+        ];
+
+        for (backend, placement, expected) in table {
+            let multiplexer = Multiplexer {
+                backend,
+                placement,
+                size: 30,
+            };
+            let commands = multiplexer.commands(&args, "/proj");
+            assert_eq!(commands, expected, "{:?} {:?}", backend, placement);
+        }
+    }
+
+    #[test]
+    fn zellij_window_without_a_program_only_opens_a_tab() {
+        let multiplexer = Multiplexer {
+            backend: Backend::Zellij,
+            placement: Placement::Window,
+            size: 30,
+        };
+        assert_eq!(
+            multiplexer.commands(&[], "/proj"),
+            [["zellij", "action", "new-tab", "--cwd", "/proj"]]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::vuit::editor::{Layout, Position};
+use crate::vuit::utils::{absolute_path, expand_tilde};
+use rmpv::Value;
+use std::{
+    env,
+    io::{self, Read, Write},
+    net::TcpStream,
+    os::unix::net::UnixStream,
+    path::Path,
+    time::Duration,
+};
+
+// Constants
+const RPC_TIMEOUT: Duration = Duration::from_secs(2);
+const REQUEST_ID: u32 = 1;
+// Largest message read back, a bigger one is an error instead of an allocation
+const MAX_MESSAGE_LEN: u64 = 1 << 20;
+// Moves off a :terminal window first, vuit itself may be running in it
+const LEAVE_TERMINAL: &str = "if &buftype ==# 'terminal' | silent! wincmd p | endif | \
+     if &buftype ==# 'terminal' | split | endif";
+
+// Listen address of a running Neovim, the configured one first, then $NVIM of its :terminal
+pub fn address(configured: &str) -> Option<String> {
+    let address = if configured.is_empty() {
+        env::var("NVIM").ok()?
+    } else {
+        expand_tilde(configured).display().to_string()
+    };
+
+    // Sockets have to exist, anything else is tried as host:port
+    if Path::new(&address).exists() || (address.contains(':') && !address.contains('/')) {
+        Some(address)
+    } else {
+        None
+    }
+}
+
+// Makes the Neovim at the address edit the file, at a position when one is given
+pub fn open(address: &str, file: &str, position: Option<Position>) -> io::Result<()> {
+    let mut command = format!(
+        "{} | edit {}",
+        LEAVE_TERMINAL,
+        escape_fname(&absolute_path(file))
+    );
+    if let Some(position) = position {
+        command.push_str(&format!(
+            " | call cursor({}, {})",
+            position.line.max(1),
+            position.col.unwrap_or(1).max(1)
+        ));
+    }
+
+    call(address, "nvim_command", &[&command])
+}
+
+// Makes the Neovim at the address open every file, laid out the way vim's flags would
+pub fn open_many(address: &str, files: &[String], layout: Layout) -> io::Result<()> {
+    let command = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let verb = match layout {
+                Layout::Buffers if i == 0 => "edit",
+                Layout::Buffers => "badd",
+                Layout::Tabs => "tabedit",
+                Layout::Splits => "split",
+                Layout::Vsplits => "vsplit",
+            };
+            format!("{} {}", verb, escape_fname(&absolute_path(file)))
+        })
+        .collect::<Vec<String>>()
+        .join(" | ");
+
+    call(
+        address,
+        "nvim_command",
+        &[&format!("{} | {}", LEAVE_TERMINAL, command)],
+    )
+}
+
+// Makes the Neovim at the address load an errorfile as its quickfix list and show it
+pub fn open_quickfix(address: &str, errorfile: &str) -> io::Result<()> {
+    let command = format!(
+        "{} | cfile {} | copen",
+        LEAVE_TERMINAL,
+        escape_fname(errorfile)
+    );
+    call(address, "nvim_command", &[&command])
+}
+
+// Sends one msgpack-RPC request and waits for its response
+fn call(address: &str, method: &str, args: &[&str]) -> io::Result<()> {
+    let request = Value::Array(vec![
+        Value::from(0), // request type
+        Value::from(REQUEST_ID),
+        Value::from(method),
+        Value::Array(args.iter().map(|arg| Value::from(*arg)).collect()),
+    ]);
+    let mut bytes = Vec::new();
+    rmpv::encode::write_value(&mut bytes, &request)?;
+
+    let response = if Path::new(address).exists() {
+        let mut stream = UnixStream::connect(address)?;
+        stream.set_read_timeout(Some(RPC_TIMEOUT))?;
+        stream.write_all(&bytes)?;
+        read_response(&mut stream)?
+    } else {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(RPC_TIMEOUT))?;
+        stream.write_all(&bytes)?;
+        read_response(&mut stream)?
+    };
+
+    match response {
+        Value::Nil => Ok(()),
+        error => Err(io::Error::other(error_message(&error))),
+    }
+}
+
+// The error part of the [1, id, error, result] response to our request
+fn read_response(stream: &mut impl Read) -> io::Result<Value> {
+    loop {
+        let message = rmpv::decode::read_value(&mut stream.by_ref().take(MAX_MESSAGE_LEN))?;
+        let Value::Array(mut parts) = message else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "response is not an array",
+            ));
+        };
+        // Notifications can arrive before the response
+        if parts.len() == 4
+            && parts[0].as_u64() == Some(1)
+            && parts[1].as_u64() == Some(REQUEST_ID as u64)
+        {
+            return Ok(parts.swap_remove(2));
+        }
+    }
+}
+
+fn error_message(error: &Value) -> String {
+    // Neovim sends [type, message]
+    let message = match error {
+        Value::Array(parts) => parts.last(),
+        error => Some(error),
+    };
+    message
+        .and_then(Value::as_str)
+        .unwrap_or("Neovim returned an error")
+        .to_string()
+}
+
+// Backslash escapes the characters :edit would otherwise expand, like fnameescape()
+fn escape_fname(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if " \t\n*?[{`$\\%#'\"|!<".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    if escaped.starts_with(['-', '+']) {
+        escaped.insert(0, '\\');
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::net::UnixListener, thread};
+
+    // Answers one request on a socket with the given messages, handing back the request
+    fn serve(replies: Vec<Value>) -> (tempfile::TempDir, String, thread::JoinHandle<Value>) {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("nvim.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = rmpv::decode::read_value(&mut stream).unwrap();
+            for reply in replies {
+                rmpv::encode::write_value(&mut stream, &reply).unwrap();
+            }
+            request
+        });
+        (dir, socket.to_str().unwrap().to_string(), server)
+    }
+
+    fn response(error: Value) -> Value {
+        Value::Array(vec![
+            Value::from(1),
+            Value::from(REQUEST_ID),
+            error,
+            Value::Nil,
+        ])
+    }
+
+    #[test]
+    fn requests_round_trip_over_a_socket() {
+        let notification = Value::Array(vec![
+            Value::from(2),
+            Value::from("redraw"),
+            Value::Array(vec![]),
+        ]);
+        let (_dir, socket, server) = serve(vec![notification, response(Value::Nil)]);
+
+        call(&socket, "nvim_command", &["edit a.rs"]).unwrap();
+        assert_eq!(
+            server.join().unwrap(),
+            Value::Array(vec![
+                Value::from(0),
+                Value::from(REQUEST_ID),
+                Value::from("nvim_command"),
+                Value::Array(vec![Value::from("edit a.rs")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn error_responses_carry_neovim_message() {
+        let error = Value::Array(vec![
+            Value::from(0),
+            Value::from("E492: Not an editor command"),
+        ]);
+        let (_dir, socket, server) = serve(vec![response(error)]);
+
+        let result = call(&socket, "nvim_command", &["bogus"]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "E492: Not an editor command"
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn oversized_messages_are_refused() {
+        let huge = response(Value::from("x".repeat(MAX_MESSAGE_LEN as usize)));
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &huge).unwrap();
+
+        assert!(read_response(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn file_names_are_escaped_for_edit() {
+        assert_eq!(escape_fname("/tmp/a b%#.rs"), r"/tmp/a\ b\%\#.rs");
+        assert_eq!(escape_fname("+x"), r"\+x");
+    }
+}
@@ -0,0 +1,202 @@
+use crate::vuit::utils::{absolute_path, clean_utf8_content, split_search_result};
+use itertools::Itertools;
+use regex::Regex;
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::Path,
+};
+use tempfile::NamedTempFile;
+
+// Writes path:line:text entries to a new temporary errorfile vim reads with its default
+// errorformat. The file is removed once the returned handle is dropped
+pub fn write_errorfile(entries: &[String]) -> io::Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("vuit-quickfix-")
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(errorfile_content(entries).as_bytes())?;
+    Ok(file)
+}
+
+// Entries without a line are left out
+fn errorfile_content(entries: &[String]) -> String {
+    let mut content = String::new();
+    for entry in entries {
+        if split_search_result(entry).1.is_none() {
+            continue;
+        }
+        let Some((file, rest)) = entry.split_once(':') else {
+            continue;
+        };
+        // The editor may not be running in vuit's directory
+        content.push_str(&format!("{}:{}\n", absolute_path(file), rest));
+    }
+    content
+}
+
+// Reads path:line[:col]:message locations from a compiler log or errorfile into
+// entries like the ones string search finds. Relative paths are looked up from vuit's
+// directory, then from the errorfile's, lines that name no existing file are skipped
+pub fn read_errorfile(path: &Path) -> io::Result<Vec<String>> {
+    let content = fs::read(path)?;
+    let content = String::from_utf8_lossy(&content);
+
+    // make, gcc, cargo --message-format short and vim's own errorfiles
+    let plain =
+        Regex::new(r"^(?P<file>[^\s:|][^:|]*):(?P<line>\d+):(?:(?P<col>\d+):)?\s*(?P<msg>.*)$")
+            .expect("Invalid errorfile pattern");
+    // Lines saved from vim's quickfix window
+    let window = Regex::new(
+        r"^(?P<file>[^|]+)\|(?P<line>\d+)(?: col (?P<col>\d+))?(?: \w+(?: \d+)?)?\|\s?(?P<msg>.*)$",
+    )
+    .expect("Invalid errorfile pattern");
+    // Long rustc diagnostics, the message is on the line before the arrow
+    let arrow = Regex::new(r"^\s*--> (?P<file>[^\s:]+):(?P<line>\d+):(?P<col>\d+)")
+        .expect("Invalid errorfile pattern");
+    let escapes = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").expect("Invalid escape pattern");
+
+    let cwd = env::current_dir().unwrap_or_default();
+    let log_dir = path.parent().unwrap_or(Path::new(""));
+    let mut entries = Vec::new();
+    let mut message = String::new();
+
+    for line in content.lines() {
+        let line = escapes.replace_all(line, "");
+
+        let Some(caps) = plain
+            .captures(&line)
+            .or_else(|| window.captures(&line))
+            .or_else(|| arrow.captures(&line))
+        else {
+            if line.starts_with("error") || line.starts_with("warning") {
+                message = line.to_string();
+            }
+            continue;
+        };
+
+        let file = caps["file"].trim();
+        let Some(file) = [Path::new(file).to_path_buf(), log_dir.join(file)]
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+        else {
+            continue;
+        };
+        // Absolute paths inside the project read like search results
+        let file = file
+            .strip_prefix(&cwd)
+            .map(|relative| relative.display().to_string())
+            .unwrap_or_else(|_| file.display().to_string());
+
+        let msg = caps
+            .name("msg")
+            .map_or(message.as_str(), |msg| msg.as_str());
+        let entry = match caps.name("col") {
+            Some(col) => format!("{}:{}:{}:{}", file, &caps["line"], col.as_str(), msg),
+            None => format!("{}:{}:{}", file, &caps["line"], msg),
+        };
+        entries.push(clean_utf8_content(&entry));
+    }
+
+    Ok(entries.into_iter().unique().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_entries_have_absolute_paths_and_a_line() {
+        let entries = [
+            "src/main.rs:12:fn main() {".to_string(),
+            "/tmp/a.rs:3:7:let x = 1;".to_string(),
+            "README.md".to_string(),
+            "notes.txt:abc:text".to_string(),
+        ];
+        let cwd = env::current_dir().unwrap();
+
+        assert_eq!(
+            errorfile_content(&entries),
+            format!(
+                "{}:12:fn main() {{\n/tmp/a.rs:3:7:let x = 1;\n",
+                cwd.join("src/main.rs").display()
+            )
+        );
+    }
+
+    #[test]
+    fn each_errorfile_is_new_and_removed_when_dropped() {
+        let entries = ["/tmp/a.rs:1:x".to_string()];
+        let first = write_errorfile(&entries).unwrap();
+        let second = write_errorfile(&entries).unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(fs::read_to_string(first.path()).unwrap(), "/tmp/a.rs:1:x\n");
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    // Reads a log written next to a src/main.rs, returning entries relative to that directory
+    fn read_log(log: &str) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let path = dir.path().join("build.log");
+        fs::write(&path, log).unwrap();
+
+        let prefix = format!("{}/", dir.path().display());
+        read_errorfile(&path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.replace(&prefix, ""))
+            .collect()
+    }
+
+    #[test]
+    fn gcc_and_cargo_short_lines_are_read() {
+        assert_eq!(
+            read_log(
+                "make: Entering directory\nsrc/main.rs:4:10: error: expected ';'\nsrc/main.rs:9: warning: unused\n"
+            ),
+            [
+                "src/main.rs:4:10:error: expected ';'",
+                "src/main.rs:9:warning: unused"
+            ]
+        );
+    }
+
+    #[test]
+    fn rustc_arrows_take_the_message_above() {
+        let log =
+            "\x1b[1;31merror[E0425]\x1b[0m: cannot find value `x`\n  --> src/main.rs:2:13\n   |\n";
+        assert_eq!(
+            read_log(log),
+            ["src/main.rs:2:13:error[E0425]: cannot find value `x`"]
+        );
+    }
+
+    #[test]
+    fn vim_window_lines_are_read_with_or_without_a_type() {
+        assert_eq!(
+            read_log(
+                "src/main.rs|3 col 5| plain\nsrc/main.rs|4 col 1 error| typed\nsrc/main.rs|5 warning 12| numbered\n"
+            ),
+            [
+                "src/main.rs:3:5:plain",
+                "src/main.rs:4:1:typed",
+                "src/main.rs:5:numbered"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_files_and_repeats_are_skipped() {
+        assert_eq!(
+            read_log(
+                "src/gone.rs:1:1: error: x\nsrc/main.rs:1:1: error: y\nsrc/main.rs:1:1: error: y\n"
+            ),
+            ["src/main.rs:1:1:error: y"]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::vuit::search::{SearchOptions, replace_matches};
+use crate::vuit::utils::content_hash;
+use grep::regex::RegexMatcher;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    process,
+    time::SystemTime,
+};
+
+// Modification time and content hash of a file when it was searched
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub fn capture(path: &Path, content: &[u8]) -> Self {
+        Self {
+            modified: fs::metadata(path).and_then(|meta| meta.modified()).ok(),
+            hash: content_hash(content),
+        }
+    }
+}
+
+// Splits content into lines, each keeping its own terminator
+pub fn split_lines(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    content.split_inclusive(|byte| *byte == b'\n')
+}
+
+// Separates a line from its "\n" or "\r\n" terminator
+pub fn strip_terminator(line: &[u8]) -> (&[u8], &[u8]) {
+    let terminator_len = if line.ends_with(b"\r\n") {
+        2
+    } else if line.ends_with(b"\n") {
+        1
+    } else {
+        0
+    };
+    line.split_at(line.len() - terminator_len)
+}
+
+// Replaces the accepted matches, keyed by 1-based line and then by the byte offset the
+// match starts at in that line. Every other byte is kept as is
+pub fn rewrite_lines(
+    content: &[u8],
+    accepted: &HashMap<usize, HashSet<usize>>,
+    matcher: &RegexMatcher,
+    replacement: &str,
+    opts: &SearchOptions,
+) -> Vec<u8> {
+    let mut rewritten = Vec::with_capacity(content.len());
+
+    for (index, line) in split_lines(content).enumerate() {
+        if let Some(starts) = accepted.get(&(index + 1)) {
+            let (body, terminator) = strip_terminator(line);
+            rewritten.extend(replace_matches(matcher, body, replacement, opts, |range| {
+                starts.contains(&range.start)
+            }));
+            rewritten.extend_from_slice(terminator);
+        } else {
+            rewritten.extend_from_slice(line);
+        }
+    }
+
+    rewritten
+}
+
+// Writes to a temp file next to the target and renames it into place, keeping permissions
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let permissions = fs::metadata(&path)?.permissions();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(".{}.vuit-{}.tmp", file_name, process::id()));
+
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::set_permissions(&tmp_path, permissions))
+        .and_then(|_| fs::rename(&tmp_path, &path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vuit::search::build_matcher;
+
+    // Rewrites every match on the given lines
+    fn rewrite(content: &[u8], lines: &[usize]) -> Vec<u8> {
+        let accepted = lines
+            .iter()
+            .map(|&line| (line, (0..content.len()).collect()))
+            .collect();
+        rewrite_accepted(content, &accepted)
+    }
+
+    // Rewrites only the matches starting at the accepted offsets of each line
+    fn rewrite_accepted(content: &[u8], accepted: &HashMap<usize, HashSet<usize>>) -> Vec<u8> {
+        let opts = SearchOptions::default();
+        let matcher = build_matcher("old", &opts).unwrap();
+        rewrite_lines(content, accepted, &matcher, "new", &opts)
+    }
+
+    #[test]
+    fn keeps_crlf_terminators() {
+        assert_eq!(
+            rewrite(b"old\r\nold\r\nold\r\n", &[1, 3]),
+            b"new\r\nold\r\nnew\r\n"
+        );
+    }
+
+    #[test]
+    fn keeps_a_missing_final_newline() {
+        assert_eq!(rewrite(b"a\nold", &[2]), b"a\nnew");
+        assert_eq!(rewrite(b"old\nold\n", &[2]), b"old\nnew\n");
+    }
+
+    #[test]
+    fn mixed_terminators_are_left_alone() {
+        assert_eq!(rewrite(b"old\r\nold\nold", &[1, 2, 3]), b"new\r\nnew\nnew");
+    }
+
+    #[test]
+    fn strip_terminator_splits_off_the_line_ending() {
+        assert_eq!(strip_terminator(b"x\r\n"), (&b"x"[..], &b"\r\n"[..]));
+        assert_eq!(strip_terminator(b"x\n"), (&b"x"[..], &b"\n"[..]));
+        assert_eq!(strip_terminator(b"x"), (&b"x"[..], &b""[..]));
+    }
+
+    #[test]
+    fn write_atomic_replaces_content_and_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.sh");
+        fs::write(&path, "old\r\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&path, b"new\r\n").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new\r\n");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        // Only the file itself is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn write_atomic_fails_for_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(write_atomic(&dir.path().join("missing"), b"x").is_err());
+    }
+
+    #[test]
+    fn rejected_matches_on_a_line_are_kept() {
+        let accepted = HashMap::from([(1, HashSet::from([4])), (2, HashSet::new())]);
+        assert_eq!(
+            rewrite_accepted(b"old old old\nold\n", &accepted),
+            b"old new old\nold\n"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use grep::regex::{Error, RegexMatcher, RegexMatcherBuilder};
+use grep_matcher::{Captures, Matcher};
+use std::ops::Range;
+
+// Case Sensitivity Modes
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub enum CaseMode {
+    #[default]
+    Insensitive,
+    Sensitive,
+    Smart,
+}
+
+impl CaseMode {
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Insensitive => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Insensitive,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            CaseMode::Insensitive => "ignore-case",
+            CaseMode::Sensitive => "match-case",
+            CaseMode::Smart => "smart-case",
+        }
+    }
+}
+
+// String Search Options
+#[derive(Default, Clone, Copy)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case: CaseMode,
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    // Short label of the active flags for the search bar title
+    pub fn label(&self) -> String {
+        let mode = if self.regex { "regex" } else { "literal" };
+        let mut flags = vec![mode, self.case.label()];
+        if self.whole_word {
+            flags.push("word");
+        }
+        format!("[{}]", flags.join("|"))
+    }
+}
+
+// Builds the matcher shared by the string search and replace paths
+pub fn build_matcher(query: &str, opts: &SearchOptions) -> Result<RegexMatcher, Error> {
+    RegexMatcherBuilder::new()
+        .case_insensitive(opts.case == CaseMode::Insensitive)
+        .case_smart(opts.case == CaseMode::Smart)
+        .word(opts.whole_word)
+        .fixed_strings(!opts.regex)
+        .line_terminator(Some(b'\n'))
+        .build(query)
+}
+
+// Replaces every match in a line, expanding $1/${name} capture references in regex mode
+pub fn replace_bytes(
+    matcher: &RegexMatcher,
+    haystack: &[u8],
+    replacement: &str,
+    opts: &SearchOptions,
+) -> Vec<u8> {
+    replace_matches(matcher, haystack, replacement, opts, |_| true)
+}
+
+// Replaces the matches whose range is kept, the others stay as they are
+pub fn replace_matches(
+    matcher: &RegexMatcher,
+    haystack: &[u8],
+    replacement: &str,
+    opts: &SearchOptions,
+    mut keep: impl FnMut(Range<usize>) -> bool,
+) -> Vec<u8> {
+    let mut dst = Vec::with_capacity(haystack.len());
+
+    let replaced = if opts.regex {
+        matcher.new_captures().and_then(|mut caps| {
+            matcher.replace_with_captures(haystack, &mut caps, &mut dst, |caps, dst| {
+                let Some(found) = caps.get(0) else {
+                    return true;
+                };
+                if keep(found.start()..found.end()) {
+                    caps.interpolate(
+                        |name| matcher.capture_index(name),
+                        haystack,
+                        replacement.as_bytes(),
+                        dst,
+                    );
+                } else {
+                    dst.extend_from_slice(&haystack[found]);
+                }
+                true
+            })
+        })
+    } else {
+        matcher.replace(haystack, &mut dst, |found, dst| {
+            if keep(found.start()..found.end()) {
+                dst.extend_from_slice(replacement.as_bytes());
+            } else {
+                dst.extend_from_slice(&haystack[found]);
+            }
+            true
+        })
+    };
+
+    match replaced {
+        Ok(()) => dst,
+        Err(_) => haystack.to_vec(),
+    }
+}
+
+// Byte ranges of every match in a line
+pub fn match_ranges(matcher: &RegexMatcher, haystack: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let _ = matcher.find_iter(haystack, |found| {
+        ranges.push(found.start()..found.end());
+        true
+    });
+    ranges
+}
+
+// String form of replace_bytes for display
+pub fn replace_line(
+    matcher: &RegexMatcher,
+    line: &str,
+    replacement: &str,
+    opts: &SearchOptions,
+) -> String {
+    String::from_utf8_lossy(&replace_bytes(matcher, line.as_bytes(), replacement, opts))
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(query: &str, line: &str, replacement: &str, opts: SearchOptions) -> String {
+        let matcher = build_matcher(query, &opts).unwrap();
+        replace_line(&matcher, line, replacement, &opts)
+    }
+
+    #[test]
+    fn literal_mode_does_not_expand_captures_or_metacharacters() {
+        let opts = SearchOptions::default();
+        assert_eq!(replace("a.b", "a.b axb", "$1", opts), "$1 axb");
+    }
+
+    #[test]
+    fn regex_mode_expands_numbered_and_named_captures() {
+        let opts = SearchOptions {
+            regex: true,
+            case: CaseMode::Sensitive,
+            ..Default::default()
+        };
+        assert_eq!(
+            replace(r"get_(\w+)", "get_user(get_id)", "fetch_$1", opts),
+            "fetch_user(fetch_id)"
+        );
+        assert_eq!(
+            replace(
+                r"(?P<key>\w+)=(?P<value>\w+)",
+                "a=1 b=2",
+                "${value}=${key}",
+                opts
+            ),
+            "1=a 2=b"
+        );
+    }
+
+    #[test]
+    fn whole_word_skips_matches_inside_words() {
+        let opts = SearchOptions {
+            whole_word: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            replace("id", "id idle uid id", "key", opts),
+            "key idle uid key"
+        );
+    }
+
+    #[test]
+    fn case_modes() {
+        let insensitive = SearchOptions::default();
+        assert_eq!(replace("foo", "Foo foo FOO", "x", insensitive), "x x x");
+
+        let sensitive = SearchOptions {
+            case: CaseMode::Sensitive,
+            ..Default::default()
+        };
+        assert_eq!(replace("foo", "Foo foo FOO", "x", sensitive), "Foo x FOO");
+
+        // Smart case ignores case until the query has an upper case letter
+        let smart = SearchOptions {
+            case: CaseMode::Smart,
+            ..Default::default()
+        };
+        assert_eq!(replace("foo", "Foo foo FOO", "x", smart), "x x x");
+        assert_eq!(replace("Foo", "Foo foo FOO", "x", smart), "x foo FOO");
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let opts = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        assert!(build_matcher("(unclosed", &opts).is_err());
+    }
+
+    #[test]
+    fn only_kept_matches_are_replaced() {
+        let opts = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        let matcher = build_matcher(r"(\w)=1", &opts).unwrap();
+        let line = b"a=1 b=1 c=1";
+
+        assert_eq!(match_ranges(&matcher, line), [0..3, 4..7, 8..11]);
+        let kept = replace_matches(&matcher, line, "$1=2", &opts, |range| range.start != 4);
+        assert_eq!(kept, b"a=2 b=1 c=2");
+
+        let literal = SearchOptions::default();
+        let matcher = build_matcher("x", &literal).unwrap();
+        let kept = replace_matches(&matcher, b"x x x", "y", &literal, |range| range == (2..3));
+        assert_eq!(kept, b"x y x");
+    }
+}
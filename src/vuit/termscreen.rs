@@ -0,0 +1,354 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::collections::VecDeque;
+
+// Constants
+const SCROLLBACK_LINES: usize = 5000;
+// vt100 can only show one screenful of its own scrollback, so it only needs a little
+const PARSER_SCROLLBACK: usize = 512;
+
+// Screen model of the shell plus a bounded ring of the lines scrolled off its top
+#[derive(Default)]
+pub struct TermScreen {
+    parser: vt100::Parser,
+    history: VecDeque<Line<'static>>,
+    scroll: usize,
+}
+
+impl TermScreen {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            parser: vt100::Parser::new(rows, cols, PARSER_SCROLLBACK),
+            history: VecDeque::new(),
+            scroll: 0,
+        }
+    }
+
+    pub fn screen(&self) -> &vt100::Screen {
+        self.parser.screen()
+    }
+
+    pub fn set_size(&mut self, rows: u16, cols: u16) {
+        self.parser.set_size(rows, cols);
+    }
+
+    pub fn process(&mut self, bytes: &[u8]) {
+        // Feed pieces that cannot scroll more than a screenful, so every line can be read back.
+        // Line feeds and wraps both scroll, escape sequences are counted as text so a piece
+        // may end early but never late
+        let (rows, cols) = self.screen().size();
+        let limit = (rows as usize).saturating_sub(1).max(1);
+        let cols = cols.max(1) as usize;
+        let mut start = 0;
+        let mut feeds = 0;
+        let mut column = 0;
+        for (i, &byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' | 0x0b | 0x0c => {
+                    feeds += 1;
+                    column = 0;
+                }
+                b'\r' => column = 0,
+                _ => {
+                    column += 1;
+                    if column > cols {
+                        feeds += 1;
+                        column = 1;
+                    }
+                }
+            }
+            if feeds >= limit {
+                self.process_piece(&bytes[start..=i]);
+                start = i + 1;
+                feeds = 0;
+            }
+        }
+        if start < bytes.len() {
+            self.process_piece(&bytes[start..]);
+        }
+    }
+
+    fn process_piece(&mut self, bytes: &[u8]) {
+        let alternate = self.screen().alternate_screen();
+
+        // vt100 bumps a non-zero scrollback offset once for every line that scrolls off
+        self.parser.set_scrollback(1);
+        let marked = self.screen().scrollback();
+        self.parser.process(bytes);
+
+        let scrolled = if self.screen().alternate_screen() != alternate {
+            0
+        } else if marked == 1 {
+            self.screen().scrollback().saturating_sub(1)
+        } else {
+            // Nothing had scrolled off before, so all of it is new
+            self.parser.set_scrollback(usize::MAX);
+            self.screen().scrollback()
+        };
+
+        let scrolled = scrolled.min(self.screen().size().0 as usize);
+        self.parser.set_scrollback(scrolled);
+        for row in 0..scrolled as u16 {
+            let line = row_line(self.screen(), row, None);
+            self.history.push_back(line);
+        }
+        self.parser.set_scrollback(0);
+
+        while self.history.len() > SCROLLBACK_LINES {
+            self.history.pop_front();
+        }
+
+        // A scrolled back view stays on the lines it was showing
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + scrolled).min(self.history.len());
+        }
+    }
+
+    // Lines between the view and the live screen
+    pub fn scrollback(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.history.len());
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    // Plain text of the history followed by the live screen, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        let cols = self.screen().size().1;
+        self.history
+            .iter()
+            .map(line_text)
+            .chain(self.screen().rows(0, cols))
+            .collect()
+    }
+
+    // Range of lines() shown in a view this tall
+    pub fn view_range(&self, height: usize) -> (usize, usize) {
+        let total = self.history.len() + self.screen().size().0 as usize;
+        let bottom = total - self.scroll;
+        (bottom.saturating_sub(height), bottom)
+    }
+
+    // Scrolls just enough to bring a line of lines() into view
+    pub fn reveal(&mut self, line: usize, height: usize) {
+        let (top, bottom) = self.view_range(height);
+        if line < top || line >= bottom {
+            let total = self.history.len() + self.screen().size().0 as usize;
+            self.scroll = total.saturating_sub(line + height).min(self.history.len());
+        }
+    }
+
+    // Styled lines of the view, with one line of lines() optionally highlighted
+    pub fn view(&self, height: usize, mark: Option<(usize, Style)>) -> Vec<Line<'static>> {
+        let screen = self.screen();
+        let (top, bottom) = self.view_range(height);
+        let cursor = (self.scroll == 0 && !screen.hide_cursor()).then(|| screen.cursor_position());
+
+        (top..bottom)
+            .map(|index| {
+                let mut line = match index.checked_sub(self.history.len()) {
+                    Some(row) => row_line(screen, row as u16, cursor),
+                    None => self.history[index].clone(),
+                };
+                if let Some((mark_line, style)) = mark
+                    && mark_line == index
+                {
+                    for span in &mut line.spans {
+                        span.style = span.style.patch(style);
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+fn row_line(screen: &vt100::Screen, row: u16, cursor: Option<(u16, u16)>) -> Line<'static> {
+    let cols = screen.size().1;
+    let mut spans: Vec<Span<'static>> = Vec::new();
+
+    for col in 0..cols {
+        let Some(cell) = screen.cell(row, col) else {
+            continue;
+        };
+        if cell.is_wide_continuation() {
+            continue;
+        }
+
+        let mut style = cell_style(cell);
+        if cursor == Some((row, col)) {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+
+        let contents = if cell.has_contents() {
+            cell.contents()
+        } else {
+            " ".to_string()
+        };
+
+        // Merge runs of identically styled cells into one span
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(&contents),
+            _ => spans.push(Span::styled(contents, style)),
+        }
+    }
+
+    Line::from(spans)
+}
+
+fn line_text(line: &Line) -> String {
+    let text: String = line
+        .spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    text.trim_end().to_string()
+}
+
+fn cell_style(cell: &vt100::Cell) -> Style {
+    let mut style = Style::default();
+    if let Some(fg) = convert_color(cell.fgcolor()) {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = convert_color(cell.bgcolor()) {
+        style = style.bg(bg);
+    }
+    if cell.bold() {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if cell.italic() {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if cell.underline() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if cell.inverse() {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    style
+}
+
+// Default colors are left unset so the pane style shows through
+fn convert_color(color: vt100::Color) -> Option<Color> {
+    match color {
+        vt100::Color::Default => None,
+        vt100::Color::Idx(index) => Some(Color::Indexed(index)),
+        vt100::Color::Rgb(r, g, b) => Some(Color::Rgb(r, g, b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(line_text).collect()
+    }
+
+    fn numbered(count: usize) -> Vec<u8> {
+        (1..=count)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            .into_bytes()
+    }
+
+    #[test]
+    fn lines_scrolled_off_the_top_are_kept() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(5));
+
+        assert_eq!(screen.lines(), ["1", "2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn output_larger_than_a_screen_loses_no_lines() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(20));
+
+        let expected: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+        assert_eq!(screen.lines(), expected);
+    }
+
+    #[test]
+    fn wrapped_output_loses_no_lines() {
+        let mut screen = TermScreen::new(3, 10);
+        let output: Vec<String> = (0..10)
+            .map(|n| format!("{}abcdefghijklmnopqrstuvwx", n))
+            .collect();
+        screen.process(format!("{}\r\n", output.join("\r\n")).as_bytes());
+
+        // Each line wraps over three rows, read back they join up again
+        let text: String = screen.lines().concat();
+        for line in &output {
+            assert!(text.contains(line.as_str()), "{} is missing", line);
+        }
+    }
+
+    #[test]
+    fn scrollback_is_bounded() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(SCROLLBACK_LINES + 10));
+
+        let lines = screen.lines();
+        assert_eq!(lines.len(), SCROLLBACK_LINES + 3);
+        assert_eq!(lines.last().unwrap(), &(SCROLLBACK_LINES + 10).to_string());
+    }
+
+    #[test]
+    fn scrolled_back_view_stays_on_its_lines() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(5));
+        screen.scroll_by(1);
+        assert_eq!(text(&screen.view(3, None)), ["2", "3", "4"]);
+
+        screen.process(b"\r\n6\r\n7");
+        assert_eq!(screen.scrollback(), 3);
+        assert_eq!(text(&screen.view(3, None)), ["2", "3", "4"]);
+
+        screen.scroll_to_bottom();
+        assert_eq!(text(&screen.view(3, None)), ["5", "6", "7"]);
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_oldest_line() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(5));
+        screen.scroll_by(100);
+        assert_eq!(screen.scrollback(), 2);
+        screen.scroll_by(-100);
+        assert_eq!(screen.scrollback(), 0);
+    }
+
+    #[test]
+    fn alternate_screen_output_stays_out_of_the_scrollback() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(b"shell\r\n");
+        screen.process(b"\x1b[?1049h");
+        screen.process(&numbered(10));
+        screen.process(b"\x1b[?1049l");
+
+        assert_eq!(screen.lines()[0], "shell");
+        assert!(!screen.lines().contains(&"10".to_string()));
+    }
+
+    #[test]
+    fn reveal_scrolls_a_line_into_view() {
+        let mut screen = TermScreen::new(3, 10);
+        screen.process(&numbered(10));
+        screen.reveal(0, 3);
+        assert_eq!(text(&screen.view(3, None)), ["1", "2", "3"]);
+        screen.reveal(9, 3);
+        assert_eq!(screen.scrollback(), 0);
+    }
+}
@@ -1,16 +1,16 @@
 use crate::vuit::{Context, Vuit};
 use ratatui::{
+    DefaultTerminal, Frame,
     layout::{Constraint, Direction, Layout},
     prelude::Rect,
     style::{Color, Modifier, Style},
     symbols::border,
     text::{Line, Text},
     widgets::{Block, List, Paragraph},
-    DefaultTerminal, Frame,
 };
 use std::sync::atomic::Ordering;
 
-use crate::vuit::contexts::{fileviewer, stringsearch, terminal};
+use crate::vuit::contexts::{fileviewer, replacereview, stringsearch, tasks, terminal};
 use crate::vuit::utils::grab_config_color;
 use crate::vuit::{
     HELP_TEXT_BOX_NUM_LINES, RECENT_BUFFERS_NUM_LINES, SEARCH_BAR_NUM_LINES, TERMINAL_NUM_LINES,
@@ -33,10 +33,10 @@ const COLORS: &[&str] = &[
 ];
 
 pub fn dispatch_render(app: &mut Vuit, frame: &mut Frame) {
-    let (chunks, _content_lines) = make_main_layout(app, frame);
+    let (chunks, _content_lines) = make_main_layout(app.switch_context, frame.area());
     let top_chunks = make_top_chunks(app, &chunks);
     let left_chunks = make_left_chunks(&top_chunks);
-    let search_terminal_chunks = make_search_terminal_chunks(app, &chunks);
+    let search_terminal_chunks = make_search_terminal_chunks(app.switch_context, &chunks);
     let search_split_help_chunks = make_search_split_help_chunks(&search_terminal_chunks);
 
     fileviewer::render(app, frame, &left_chunks);
@@ -57,9 +57,15 @@ pub fn dispatch_render(app: &mut Vuit, frame: &mut Frame) {
             stringsearch::render(app, frame, &search_terminal_chunks);
             render_search_progress_display(app, frame, &search_terminal_chunks);
         }
+        Context::Replacereview => {
+            replacereview::render(app, frame, &search_terminal_chunks);
+        }
         Context::Terminal => {
             terminal::render(app, frame, &search_terminal_chunks);
         }
+        Context::Tasks => {
+            tasks::render(app, frame, &search_terminal_chunks);
+        }
         Context::Help => {
             render_help_menu(app, frame, &search_terminal_chunks);
         }
@@ -70,7 +76,12 @@ fn render_recent_files(app: &mut Vuit, f: &mut Frame, chunks: &[Rect]) {
     let block = Block::bordered()
         .title(Line::from(" Recent ").centered())
         .border_set(border::ROUNDED);
-    let list = List::new(app.recent_files.to_owned())
+    let entries: Vec<String> = app
+        .recent_files
+        .iter()
+        .map(|file| format!("{}{}", app.mark_flag(file), file))
+        .collect();
+    let list = List::new(entries)
         .block(block)
         .style(Style::default().fg(grab_config_color(&app.config.colorscheme)))
         .highlight_style(
@@ -116,14 +127,47 @@ fn render_search_input(app: &mut Vuit, f: &mut Frame, chunks: &[Rect]) {
         } else {
             format!(" [FILE FILTER: {}] > {}", flt, app.typed_input)
         }
+    } else if app.switch_context == Context::Terminal
+        && let Some(query) = &app.term_history_search
+    {
+        let pick = app
+            .term_history_matches
+            .get(app.term_history_pick)
+            .map(String::as_str)
+            .unwrap_or("(no match)");
+        format!(" ({}) > {}", query, pick)
+    } else if app.switch_context == Context::Terminal && app.term_search_active {
+        let state = if app.term_search.is_empty() || app.term_search_line.is_some() {
+            ""
+        } else {
+            " (no match)"
+        };
+        format!(" /{}{}", app.term_search, state)
+    } else if app.switch_context == Context::Terminal && app.term_passthrough {
+        " Keys go straight to the terminal | <C-]> - Leave passthrough".to_string()
+    } else if app.switch_context == Context::Replacereview {
+        " Enter - Write accepted changes | Space - Toggle match | Tab - Toggle file | <C-r> - Back"
+            .to_string()
     } else {
         format!(" > {}", app.typed_input)
     };
 
-    let title = if app.switch_context == Context::Terminal {
-        " Command Line "
+    let title = if app.switch_context == Context::Terminal && app.term_history_search.is_some() {
+        " History Search - <C-r> next match | Enter accept | Esc cancel ".to_string()
+    } else if app.switch_context == Context::Terminal && app.term_search_active {
+        " Scrollback Search - Enter/<C-s> older match | Esc leave ".to_string()
+    } else if app.switch_context == Context::Terminal {
+        " Command Line ".to_string()
+    } else if app.switch_context == Context::Replacereview {
+        " Review ".to_string()
+    } else if app.switch_context == Context::Tasks {
+        " Filter Tasks ".to_string()
+    } else if app.switch_context == Context::Stringsearchreplace {
+        format!(" Replace {} ", app.replace_opts.label())
+    } else if app.switch_context == Context::Stringsearch {
+        format!(" Search {} ", app.search_opts.label())
     } else {
-        " Search "
+        " Search ".to_string()
     };
 
     let para = Paragraph::new(Text::from(filter))
@@ -188,9 +232,16 @@ fn render_file_count_display(app: &mut Vuit, f: &mut Frame, chunks: &[Rect]) {
 
 //
 fn render_search_progress_display(app: &mut Vuit, f: &mut Frame, chunks: &[Rect]) {
-    let status = if app.search_in_progress {
+    let status = if !app.search_progress_str.is_empty() {
+        format!(" [ {} ] ", app.search_progress_str)
+    } else if app.search_in_progress {
         let progress = app.search_progress.load(Ordering::Relaxed);
-        format!(" [ {} / {} ] ", progress, app.file_list.len())
+        format!(
+            " [ {} Matches | {} / {} ] ",
+            app.file_str_list.len(),
+            progress,
+            app.search_total
+        )
     } else {
         format!(" [ {} Matches ] ", app.file_str_list.len())
     };
@@ -211,8 +262,8 @@ fn render_search_progress_display(app: &mut Vuit, f: &mut Frame, chunks: &[Rect]
     let right_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(chunks[0].width.saturating_sub(30)),
-            Constraint::Length(27),
+            Constraint::Length(chunks[0].width.saturating_sub(48)),
+            Constraint::Length(45),
         ])
         .split(filecount_chunks[1]);
 
@@ -224,37 +275,75 @@ fn build_help_text() -> Vec<String> {
         "(General Commands)".into(),
         "   <C-t> - Toggle terminal window".into(),
         "   <C-f> - Toggle string search window".into(),
+        "   <C-b> - Toggle task list window".into(),
         "   <C-p> - Toggle preview window".into(),
         "   Esc   - Exit Vuit".into(),
         "(File/Recent Focus Commands)".into(),
         "   Up/Down, Ctrl-j/Ctrl-k - Navigate the file list".into(),
         "   Enter - Open selected file".into(),
         "   Tab   - Switch between recent and file windows".into(),
+        "   <C-v> - Mark the highlighted entry, <C-a> - Mark all, <C-o> - Invert the marks".into(),
+        "   Enter - With entries marked, open them all in one editor".into(),
+        "   <C-l> - Cycle opening marked files as buffers, tabs, splits or vsplits".into(),
         "(Terminal Context Commands)".into(),
         "   <C-t> - Switches focus back to the file list, but terminal session is preserved".into(),
+        "   <C-g> - Passthrough mode, every key (arrows, Tab, Esc, Ctrl combos) goes to the running program".into(),
+        "   <C-]> - Leave passthrough mode".into(),
+        "   PageUp/PageDown, mouse wheel - Scroll through the terminal scrollback".into(),
+        "   <C-s> - Incremental search of the scrollback, Enter/<C-s> steps to older matches".into(),
+        "   :dump <path> - Write the scrollback to a file, :edit - Open it in the editor".into(),
+        "   Up/Down - Recall earlier commands of this session".into(),
+        "   <C-r> - Fuzzy search the history of all sessions, Enter puts the match on the command line".into(),
+        "   :rescan - Refresh the file scan used for completion".into(),
+        "   Tab   - Fuzzy complete the current word against the project's files and directories".into(),
+        "   <C-e>/<C-y> - Open the next/previous compiler or test error location from the output".into(),
+        "   <C-o> - Open a new terminal session, <C-w> - Close the current one".into(),
+        "   <C-Left>/<C-Right> - Cycle terminal sessions, :rename <name> - Rename the current one".into(),
+        "   <C-b> - Open the task list, <C-l> - Re-run the last task".into(),
+        "(Tasks Context Commands)".into(),
+        "   Type to filter the Cargo, Makefile, package.json and .vuit/tasks.json targets".into(),
+        "   Enter - Run the highlighted task in its own terminal session".into(),
+        "   <C-l> - Re-run the last task, <C-r> - Rescan the manifests".into(),
+        "   <C-b> - Back to the file list, <C-t> - Terminal".into(),
         "(String Search Context Commands)".into(),
         "   <C-f> - Switches focus back to the file list, but search session is preserved".into(),
         "   Enter - Search for the string in the filtered file list, replace string search results with typed input, or if search is already complete, enter highlighted file".into(),
         "   <C-r> - Start search and replace for all instances of string search output".into(),
+        "   <C-e> - Toggle between literal and regex string matching".into(),
+        "   <C-s> - Cycle ignore-case, match-case and smart-case matching".into(),
+        "   <C-w> - Toggle whole-word matching".into(),
+        "   <C-c> - Cancel the running string search".into(),
+        "   <C-u> - Undo this project's last search and replace, skipping files edited since".into(),
+        "   <C-v>/<C-a>/<C-o> - Mark, mark all or invert the marks of the results, Enter opens them".into(),
+        "   <C-q> - Open the marked results, else all of them, as the editor's quickfix list".into(),
+        "   :load <path> - Load the locations of a compiler log or quickfix file as the results".into(),
+        "(String Replace Context Commands)".into(),
+        "   Enter - Review the replacement of every string search result before writing".into(),
+        "   In regex mode the replacement can use $1 or ${name} capture references".into(),
+        "   <C-g> - Re-search files skipped by the last replace because they changed, then retry".into(),
+        "(Replace Review Context Commands)".into(),
+        "   Space - Accept or reject the highlighted match".into(),
+        "   Tab   - Accept or reject every match in the highlighted file".into(),
+        "   Enter - Write the accepted changes".into(),
+        "   <C-r> - Return to the replace input without writing".into(),
         "   More commands can be found on the Vuit GitHub page.".into(),
     ]
 }
 
-fn make_main_layout(app: &Vuit, frame: &Frame) -> (Vec<Rect>, u16) {
-    let (search_lines, terminal_lines) = if app.switch_context == Context::Terminal
-        || app.switch_context == Context::Help
-        || app.switch_context == Context::Stringsearch
-        || app.switch_context == Context::Stringsearchreplace
+fn make_main_layout(context: Context, area: Rect) -> (Vec<Rect>, u16) {
+    let (search_lines, terminal_lines) = if context == Context::Terminal
+        || context == Context::Tasks
+        || context == Context::Help
+        || context == Context::Stringsearch
+        || context == Context::Stringsearchreplace
+        || context == Context::Replacereview
     {
         (SEARCH_BAR_NUM_LINES, TERMINAL_NUM_LINES)
     } else {
         (SEARCH_BAR_NUM_LINES, 0)
     };
 
-    let content_lines = frame
-        .area()
-        .height
-        .saturating_sub(search_lines + terminal_lines);
+    let content_lines = area.height.saturating_sub(search_lines + terminal_lines);
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -262,7 +351,7 @@ fn make_main_layout(app: &Vuit, frame: &Frame) -> (Vec<Rect>, u16) {
             Constraint::Length(content_lines),
             Constraint::Length(search_lines + terminal_lines),
         ])
-        .split(frame.area());
+        .split(area);
 
     (chunks.to_vec(), content_lines)
 }
@@ -298,11 +387,13 @@ fn make_left_chunks(top_chunks: &[Rect]) -> Vec<Rect> {
         .to_vec()
 }
 
-fn make_search_terminal_chunks(app: &Vuit, chunks: &[Rect]) -> Vec<Rect> {
-    if app.switch_context == Context::Stringsearch
-        || app.switch_context == Context::Terminal
-        || app.switch_context == Context::Help
-        || app.switch_context == Context::Stringsearchreplace
+fn make_search_terminal_chunks(context: Context, chunks: &[Rect]) -> Vec<Rect> {
+    if context == Context::Stringsearch
+        || context == Context::Terminal
+        || context == Context::Tasks
+        || context == Context::Help
+        || context == Context::Stringsearchreplace
+        || context == Context::Replacereview
     {
         Layout::default()
             .direction(Direction::Vertical)
@@ -317,6 +408,14 @@ fn make_search_terminal_chunks(app: &Vuit, chunks: &[Rect]) -> Vec<Rect> {
     }
 }
 
+// Rows and columns inside the Terminal pane's borders for a window of the given size
+pub fn terminal_pane_size(window_cols: u16, window_rows: u16) -> (u16, u16) {
+    let area = Rect::new(0, 0, window_cols, window_rows);
+    let (chunks, _) = make_main_layout(Context::Terminal, area);
+    let pane = make_search_terminal_chunks(Context::Terminal, &chunks)[0];
+    (pane.height.saturating_sub(2), pane.width.saturating_sub(2))
+}
+
 fn make_search_split_help_chunks(search_terminal_chunks: &[Rect]) -> Vec<Rect> {
     let help_width = HELP_TEXT_BOX_NUM_LINES;
     let search_width = search_terminal_chunks[1].width.saturating_sub(help_width);
@@ -335,15 +434,30 @@ pub fn next_colorscheme(app: &mut Vuit, terminal: &mut DefaultTerminal) {
     app.colorscheme_index = (app.colorscheme_index + 1) % COLORS.len();
     app.config.colorscheme = COLORS[app.colorscheme_index].to_string();
     app.config.highlight_color = COLORS[(app.colorscheme_index + 1) % COLORS.len()].to_string();
-    if app.config.highlight_color == "white".to_string()
-        || app.config.highlight_color == "gray".to_string()
-        || app.config.highlight_color == "lightyellow".to_string()
-        || app.config.highlight_color == "yellow".to_string()
-        || app.config.highlight_color == "lightgreen".to_string()
-        || app.config.highlight_color == "green".to_string()
+    if app.config.highlight_color == "white"
+        || app.config.highlight_color == "gray"
+        || app.config.highlight_color == "lightyellow"
+        || app.config.highlight_color == "yellow"
+        || app.config.highlight_color == "lightgreen"
+        || app.config.highlight_color == "green"
     {
         app.config.highlight_color = "lightblue".to_string();
     }
 
     let _ = terminal.draw(|frame| dispatch_render(app, frame));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_pane_fits_the_window() {
+        assert_eq!(terminal_pane_size(100, 60), (TERMINAL_NUM_LINES - 2, 98));
+
+        // Too short for the whole pane, the shell only gets the rows that are drawn
+        let (rows, cols) = terminal_pane_size(80, 12);
+        assert_eq!(cols, 78);
+        assert_eq!(rows, 12 - SEARCH_BAR_NUM_LINES - 2);
+    }
+}
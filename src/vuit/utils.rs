@@ -1,6 +1,5 @@
 use ratatui::style::Color;
-use regex::Regex;
-use std::path::PathBuf;
+use std::{env, io, path::PathBuf};
 
 // Helper Functions
 pub fn clean_utf8_content(content: &str) -> String {
@@ -9,25 +8,25 @@ pub fn clean_utf8_content(content: &str) -> String {
         .filter(|&c| c.is_ascii_graphic() || c == '\n' || c == ' ')
         .collect()
 }
-pub fn remove_ansi_escape_codes(input: &str) -> String {
-    // Create a regex to match ANSI escape sequences
-    let re = Regex::new(r"\x1b\[([0-9]{1,2};[0-9]{1,2}|[0-9]{1,2})?m").unwrap();
-    let reclean = re.replace_all(input, "");
-    let reclean = reclean.replace("\r", ""); // Remove carriage returns
-    let reclean = reclean.replace("\t", "    "); // Convert tabs to spaces
-
-    // Return the cleaned output
-    reclean.to_string()
-}
 pub fn expand_tilde(path: &str) -> PathBuf {
-    if path.starts_with("~") {
-        if let Some(home_dir) = dirs::home_dir() {
-            return home_dir.join(&path[2..]); // Replace `~` with the home directory
-        }
+    if let Some(rest) = path.strip_prefix("~")
+        && (rest.is_empty() || rest.starts_with('/'))
+        && let Some(home_dir) = dirs::home_dir()
+    {
+        return home_dir.join(rest.trim_start_matches('/')); // Replace `~` with the home directory
     }
     PathBuf::from(path)
 }
 
+// Path from the directory vuit runs in, for programs that may be running elsewhere
+pub fn absolute_path(file: &str) -> String {
+    env::current_dir()
+        .map(|cwd| cwd.join(file))
+        .unwrap_or_else(|_| PathBuf::from(file))
+        .display()
+        .to_string()
+}
+
 pub fn grab_config_color(color_str: &str) -> Color {
     match color_str.to_lowercase().as_str() {
         "lightblue" => Color::LightBlue,
@@ -45,3 +44,43 @@ pub fn grab_config_color(color_str: &str) -> Color {
         &_ => Color::LightBlue,
     }
 }
+
+// FNV-1a, stable across builds so it can be stored on disk
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Per-project directory under a base directory, named after a hash of the project path
+pub fn project_dir(base: &str) -> io::Result<PathBuf> {
+    let cwd = env::current_dir()?;
+    let name = format!("{:016x}", content_hash(cwd.as_os_str().as_encoded_bytes()));
+    Ok(expand_tilde(base).join(name))
+}
+
+// File and line of a path:line:text search result, the whole entry when it has no line
+pub fn split_search_result(entry: &str) -> (&str, Option<usize>) {
+    match entry.split_once(':') {
+        Some((file, rest)) => {
+            let line = rest.split_once(':').map(|(line, _)| line).unwrap_or(rest);
+            (file, line.parse().ok())
+        }
+        None => (entry, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilde_expands_to_the_home_directory() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("~/"), home);
+        assert_eq!(expand_tilde("~/notes.txt"), home.join("notes.txt"));
+        assert_eq!(expand_tilde("~other/x"), PathBuf::from("~other/x"));
+        assert_eq!(expand_tilde("a/~"), PathBuf::from("a/~"));
+    }
+}